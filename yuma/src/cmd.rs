@@ -0,0 +1,257 @@
+//! Every external program yuma runs goes through a [`CommandRunner`]. By
+//! default that is the [`SystemRunner`] which actually runs things but it can
+//! be swapped out per thread with [`set_runner`]. The [`ScriptedRunner`] can
+//! be used to see what a backend would run without touching the system.
+
+use crate::prelude::*;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt, io,
+    io::Write,
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use color_eyre::eyre::WrapErr;
+
+pub trait CommandRunner: fmt::Debug {
+    /// Runs a command to completion capturing what it prints.
+    fn output(&self, cmd: &mut Command) -> io::Result<Output>;
+
+    /// Runs a command attached to the terminal.
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus>;
+
+    /// Like [`CommandRunner::output`] but feeds `stdin` to the command.
+    fn output_with_stdin(&self, cmd: &mut Command, stdin: &[u8]) -> io::Result<Output>;
+}
+
+/// Runs commands for real.
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+        cmd.output()
+    }
+
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        cmd.status()
+    }
+
+    fn output_with_stdin(&self, cmd: &mut Command, stdin: &[u8]) -> io::Result<Output> {
+        let mut child = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let mut pipe = child.stdin.take().expect("stdin is piped");
+        // the child may fill stdout before it has read all of its input so
+        // both are drained at the same time
+        thread::scope(|scope| {
+            // dropping the handle closes it so the child sees the end of input
+            let writer = scope.spawn(move || pipe.write_all(stdin));
+            let output = child.wait_with_output()?;
+            writer.join().expect("stdin writer panicked")?;
+            Ok(output)
+        })
+    }
+}
+
+thread_local! {
+static RUNNER: RefCell<Arc<dyn CommandRunner>> = RefCell::new(Arc::new(SystemRunner));
+}
+
+/// Replaces the runner used on this thread.
+pub fn set_runner(runner: Arc<dyn CommandRunner>) {
+    RUNNER.with(|r| *r.borrow_mut() = runner);
+}
+
+/// The runner used on this thread.
+pub fn runner() -> Arc<dyn CommandRunner> {
+    RUNNER.with(|r| r.borrow().clone())
+}
+
+/// Runs a command and returns whatever it produced, even when it failed.
+pub(crate) fn raw_output(cmd: &mut Command) -> Result<Output> {
+    runner()
+        .output(cmd)
+        .wrap_err_with(|| format!("Failed to run {}", cmd.get_program().to_string_lossy()))
+}
+
+/// Runs a command to completion and returns what it printed. Anything but a
+/// successful exit is turned into a [`YumaError::Command`].
+pub(crate) fn output(cmd: &mut Command) -> Result<String> {
+    let out = raw_output(cmd)?;
+    if !out.status.success() {
+        return Err(YumaError::command(cmd, out.status, &out.stderr).into());
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+/// Runs a command with the given input, failing like [`output`] does.
+pub(crate) fn output_with_stdin(cmd: &mut Command, stdin: &[u8]) -> Result<String> {
+    let out = runner()
+        .output_with_stdin(cmd, stdin)
+        .wrap_err_with(|| format!("Failed to run {}", cmd.get_program().to_string_lossy()))?;
+    if !out.status.success() {
+        return Err(YumaError::command(cmd, out.status, &out.stderr).into());
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+/// Runs a command attached to the terminal so the user can follow along and
+/// answer any prompts.
+pub(crate) fn status(cmd: &mut Command) -> Result<()> {
+    let status = runner()
+        .status(cmd)
+        .wrap_err_with(|| format!("Failed to run {}", cmd.get_program().to_string_lossy()))?;
+    if !status.success() {
+        return Err(YumaError::command(cmd, status, &[]).into());
+    }
+    Ok(())
+}
+
+/// A command that was run by a [`ScriptedRunner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    /// What was fed to the command, if anything.
+    pub stdin: Option<String>,
+}
+
+impl Invocation {
+    fn new(cmd: &Command, stdin: Option<&[u8]>) -> Self {
+        Self {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            stdin: stdin.map(|s| String::from_utf8_lossy(s).into_owned()),
+        }
+    }
+
+    /// The program and its arguments joined by spaces.
+    pub fn line(&self) -> String {
+        let mut line = self.program.clone();
+        for arg in self.args.iter() {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Reply {
+    code: i32,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// Pretends to run commands. Every invocation is recorded and answered with
+/// the reply scripted for its command line, or with an empty success when
+/// there is none.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use yuma::cmd::{self, ScriptedRunner};
+/// # use yuma::prelude::*;
+/// let runner = Arc::new(ScriptedRunner::new().reply("paru -Qq", "base\nlinux\n"));
+/// cmd::set_runner(runner.clone());
+///
+/// assert_eq!(Packager::paru().list_installed().unwrap(), ["base", "linux"]);
+/// assert_eq!(runner.lines(), ["paru -Qq"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct ScriptedRunner {
+    replies: HashMap<String, Reply>,
+    calls: Mutex<Vec<Invocation>>,
+}
+
+impl ScriptedRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `line` successfully with the given stdout.
+    pub fn reply(mut self, line: impl Into<String>, stdout: impl Into<String>) -> Self {
+        let reply = Reply {
+            stdout: stdout.into().into_bytes(),
+            ..default()
+        };
+        self.replies.insert(line.into(), reply);
+        self
+    }
+
+    /// Answers `line` with an exit code and what it printed to stdout and
+    /// stderr, for commands that print their results even when they fail.
+    pub fn exit(
+        mut self,
+        line: impl Into<String>,
+        code: i32,
+        stdout: impl Into<String>,
+        stderr: impl Into<String>,
+    ) -> Self {
+        let reply = Reply {
+            code,
+            stdout: stdout.into().into_bytes(),
+            stderr: stderr.into().into_bytes(),
+        };
+        self.replies.insert(line.into(), reply);
+        self
+    }
+
+    /// Answers `line` with an exit code and stderr.
+    pub fn fail(mut self, line: impl Into<String>, code: i32, stderr: impl Into<String>) -> Self {
+        let reply = Reply {
+            code,
+            stderr: stderr.into().into_bytes(),
+            ..default()
+        };
+        self.replies.insert(line.into(), reply);
+        self
+    }
+
+    /// Everything that was run so far.
+    pub fn calls(&self) -> Vec<Invocation> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The command lines of everything that was run so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.calls().iter().map(Invocation::line).collect()
+    }
+
+    fn answer(&self, cmd: &Command, stdin: Option<&[u8]>) -> Output {
+        let invocation = Invocation::new(cmd, stdin);
+        let reply = self
+            .replies
+            .get(&invocation.line())
+            .cloned()
+            .unwrap_or_default();
+        self.calls.lock().unwrap().push(invocation);
+
+        Output {
+            // wait statuses keep the exit code in the second byte
+            status: ExitStatus::from_raw(reply.code << 8),
+            stdout: reply.stdout,
+            stderr: reply.stderr,
+        }
+    }
+}
+
+impl CommandRunner for ScriptedRunner {
+    fn output(&self, cmd: &mut Command) -> io::Result<Output> {
+        Ok(self.answer(cmd, None))
+    }
+
+    fn status(&self, cmd: &mut Command) -> io::Result<ExitStatus> {
+        Ok(self.answer(cmd, None).status)
+    }
+
+    fn output_with_stdin(&self, cmd: &mut Command, stdin: &[u8]) -> io::Result<Output> {
+        Ok(self.answer(cmd, Some(stdin)))
+    }
+}
@@ -1,9 +1,12 @@
 use crate::callbacks::{Callbacks, YumaCallbackSig};
+use crate::deriv::packager::{detect::PACKAGER_ENV, Repo};
 use crate::deriv::pkg::list::{AsPkgList, Packages};
+use crate::deriv::pkg::upgrade::{UpgradePlan, UpgradePolicy};
 use crate::deriv::srv::Services;
 use crate::prelude::*;
+use crate::prompt::{self, RunMode};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::{env, fs};
 use stub::Stub;
 
 #[derive(Debug, Serialize, Deserialize, Stub)]
@@ -16,6 +19,14 @@ pub struct YumaCtx {
     /// testing.
     #[serde(skip)]
     is_interactive: bool,
+    #[serde(skip)]
+    is_dry_run: bool,
+    /// Used for packages that don't name a packager, see
+    /// [`YumaCtx::default_packager`].
+    #[serde(skip)]
+    default_packager: Option<Packager>,
+    #[serde(skip)]
+    run_mode: RunMode,
 }
 
 impl Default for YumaCtx {
@@ -26,6 +37,9 @@ impl Default for YumaCtx {
             services: Default::default(),
             callbacks: Default::default(),
             is_interactive,
+            is_dry_run: false,
+            default_packager: None,
+            run_mode: RunMode::default(),
         }
     }
 }
@@ -54,7 +68,13 @@ impl YumaCtx {
     where
         P: AsPkgList,
     {
-        self.packages.add(pkgs.list());
+        // the environment still wins over the config
+        let requested = env::var(PACKAGER_ENV).is_ok_and(|name| !name.is_empty());
+        let default = match &self.default_packager {
+            Some(pkgr) if !requested => pkgr.clone(),
+            _ => Packager::guess(),
+        };
+        self.packages.add(pkgs.list_with_default(&default));
     }
 
     /// Alias for [`YumaCtx::add`] for a potential name change
@@ -65,6 +85,100 @@ impl YumaCtx {
         self.add(pkgs)
     }
 
+    /// Sets the packager used for packages that don't name one, instead of
+    /// detecting it from the system. Only affects packages added after this
+    /// and is itself overriden by the `YUMA_PACKAGER` environment variable.
+    ///
+    /// ```rust
+    /// use yuma::prelude::*;
+    /// let mut ctx = ctx();
+    /// # ctx.dry_run();
+    ///
+    /// ctx.default_packager(Packager::brew());
+    /// ctx.add("git");
+    /// ```
+    pub fn default_packager(&mut self, pkgr: Packager) {
+        self.default_packager = Some(pkgr);
+    }
+
+    /// Keeps packages from ever being pruned, whichever packager they come
+    /// from. This is on top of the packages each packager protects by itself
+    /// like the kernel. Names ending in `*` protect everything starting with
+    /// the rest.
+    ///
+    /// ```rust
+    /// use yuma::prelude::*;
+    /// let mut ctx = ctx();
+    /// # ctx.dry_run();
+    ///
+    /// ctx.protect(["nvidia*", "dotfiles"]);
+    /// ```
+    pub fn protect<I, S>(&mut self, names: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.packages.protect(names)
+    }
+
+    /// Also removes orphaned dependencies once undeclared packages have been
+    /// pruned, asking for each packager separately. Declared and protected
+    /// packages are never removed this way.
+    ///
+    /// ```rust
+    /// use yuma::prelude::*;
+    /// let mut ctx = ctx();
+    /// # ctx.dry_run();
+    ///
+    /// ctx.clean_orphans();
+    /// ```
+    pub fn clean_orphans(&mut self) {
+        self.packages.clean_orphans()
+    }
+
+    /// Declares a repository for a packager, like a pacman repository, a brew
+    /// tap, an apt source or a flatpak remote. Missing repositories are added
+    /// before anything is installed and the ones a packager added that aren't
+    /// declared anymore are removed with the undeclared packages.
+    ///
+    /// ```rust
+    /// use yuma::prelude::*;
+    /// use yuma::deriv::packager::Repo;
+    /// let mut ctx = ctx();
+    /// # ctx.dry_run();
+    ///
+    /// ctx.repo(Packager::brew(), Repo::new("hashicorp/tap"));
+    /// ctx.repo(
+    ///     Packager::apt(),
+    ///     Repo::new("docker")
+    ///         .url("https://download.docker.com/linux/debian")
+    ///         .key("https://download.docker.com/linux/debian/gpg")
+    ///         .option("suite", "bookworm")
+    ///         .option("components", "stable"),
+    /// );
+    /// ```
+    pub fn repo(&mut self, pkgr: Packager, repo: Repo) {
+        self.packages.add_repo(pkgr, repo)
+    }
+
+    /// Sets how questions like `Install [..] ?` are answered, see [`RunMode`].
+    /// Package managers are also told not to ask anything when this isn't
+    /// [`RunMode::Interactive`], and pruning happens even without a terminal.
+    ///
+    /// ```rust
+    /// use yuma::prelude::*;
+    /// use yuma::prompt::RunMode;
+    /// let mut ctx = ctx();
+    /// # ctx.dry_run();
+    ///
+    /// ctx.run_mode(RunMode::AssumeYes);
+    /// ```
+    pub fn run_mode(&mut self, mode: RunMode) {
+        self.is_interactive =
+            !self.is_dry_run && (mode.is_unattended() || atty::is(atty::Stream::Stdout));
+        self.run_mode = mode;
+    }
+
     /// Adds a function to a list of callbacks to be ran after the next call to
     /// update
     pub fn schedule<S, F>(&mut self, name: S, f: F)
@@ -120,7 +234,8 @@ impl YumaCtx {
         // TODO: unwind the changes when an error occurs
         log::info!("Starting Update.");
 
-        self.packages.install()?;
+        let _attended = prompt::attend(&self.run_mode);
+        self.packages.install(&self.run_mode)?;
 
         // ----------> self.services.install()
         // let mut servicer = Services::guess();
@@ -142,11 +257,41 @@ impl YumaCtx {
         Ok(())
     }
 
+    /// The upgrades [`YumaCtx::upgrade`] would apply to the packagers used by
+    /// this config.
+    pub fn upgrade_plan(&self, policy: UpgradePolicy) -> Result<UpgradePlan> {
+        self.packages.upgrade_plan(policy)
+    }
+
+    /// Upgrades the packages of every packager used by this config. The
+    /// pending upgrades allowed by `policy` are shown before anything is done.
+    /// Packagers that can't upgrade are skipped.
+    ///
+    /// ```rust,no_run
+    /// use yuma::prelude::*;
+    /// use yuma::deriv::pkg::upgrade::UpgradePolicy;
+    /// let mut ctx = ctx();
+    ///
+    /// ctx.add("nodejs".b().version("20"));
+    /// ctx.update()?;
+    /// ctx.upgrade(UpgradePolicy::Declared)?;
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub fn upgrade(&mut self, policy: UpgradePolicy) -> Result<()> {
+        log::debug!("Waiting for last cycles callbacks to end.");
+        self.callbacks.wait()?;
+
+        log::info!("Starting Upgrade.");
+        let _attended = prompt::attend(&self.run_mode);
+        self.packages.upgrade(policy, &self.run_mode)
+    }
+
     /// Sets an internal variable that singals to not cache the output of this
     /// derivation. This can allow for building a revertable version of your
     /// system or for running unit test on your config if you are ill
     pub fn dry_run(&mut self) {
         self.is_interactive = false;
+        self.is_dry_run = true;
     }
 }
 
@@ -160,6 +305,11 @@ impl Drop for YumaCtx {
         let w = fs::File::create("./.yumacache.json").unwrap();
         json::to_writer_pretty(w, self).unwrap();
 
-        self.packages.prune().unwrap();
+        // panicking here would abort when already unwinding, and a missing
+        // terminal or a failed removal shouldn't take the whole run down
+        let _attended = prompt::attend(&self.run_mode);
+        if let Err(e) = self.packages.prune(&self.run_mode) {
+            log::error!("Pruning failed: {e:#}");
+        }
     }
 }
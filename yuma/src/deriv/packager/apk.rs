@@ -0,0 +1,79 @@
+use crate::prelude::*;
+
+use std::{path::PathBuf, process::Command};
+
+use super::PackageBackend;
+
+/// The list of packages the user explicitly asked for. Everything else that is
+/// installed got pulled in as a dependency.
+pub const WORLD_PATH: &str = "/etc/apk/world";
+
+#[derive(Debug, Default)]
+pub struct ApkPackager {
+    world: Option<PathBuf>,
+}
+
+impl ApkPackager {
+    /// Creates a packager that reads the leaves from the given world file
+    /// instead of [`WORLD_PATH`].
+    pub fn with_world(world: impl Into<PathBuf>) -> Self {
+        Self {
+            world: Some(world.into()),
+        }
+    }
+
+    fn world(&self) -> PathBuf {
+        self.world
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(WORLD_PATH))
+    }
+}
+
+impl PackageBackend for ApkPackager {
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(fs::read_to_string(self.world())?
+            .split_whitespace()
+            // world entries can carry constraints like `foo>=1.2` or `foo@edge`
+            .map(|entry| {
+                entry
+                    .split(['=', '<', '>', '~', '@'])
+                    .next()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("apk").arg("info").arg("--quiet"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(super::elevated("apk").arg("add").args(pkgs))
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(super::elevated("apk").arg("del").args(pkgs))
+    }
+
+    fn removal_plan(&self, pkgs: &[super::SpecficName]) -> Result<Vec<super::SpecficName>> {
+        // lines look like `(1/2) Purging htop (3.2.2-r0)`, anything still
+        // needed by another package is left out
+        let stdout = super::output(Command::new("apk").arg("del").arg("--simulate").args(pkgs))?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.split_once(") Purging "))
+            .filter_map(|(_, rest)| rest.split_whitespace().next())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn protected(&self) -> Vec<super::SpecficName> {
+        super::protect::patterns(&["alpine-base", "linux-*", "musl", "busybox", "apk-tools"])
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
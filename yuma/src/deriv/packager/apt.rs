@@ -0,0 +1,375 @@
+use crate::prelude::*;
+
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::eyre::{bail, eyre};
+
+use super::{
+    version_matches, InstallReason, PackageBackend, PackageInfo, Repo, SpecficName, Upgrade,
+};
+
+/// Where sources are added. Only the ones starting with [`SOURCE_PREFIX`]
+/// belong to yuma.
+pub const SOURCES_DIR: &str = "/etc/apt/sources.list.d";
+/// Where the signing keys of added sources go.
+pub const KEYRING_DIR: &str = "/etc/apt/keyrings";
+const SOURCE_PREFIX: &str = "yuma-";
+
+#[derive(Debug, Default)]
+pub struct AptPackager;
+
+impl AptPackager {
+    fn source_path(name: &str) -> PathBuf {
+        PathBuf::from(SOURCES_DIR).join(format!("{SOURCE_PREFIX}{name}.list"))
+    }
+
+    fn key_path(name: &str, ext: &str) -> PathBuf {
+        PathBuf::from(KEYRING_DIR).join(format!("{SOURCE_PREFIX}{name}.{ext}"))
+    }
+
+    /// The key a source was added with, if it is still there.
+    fn existing_key(name: &str) -> Option<PathBuf> {
+        ["asc", "gpg"]
+            .into_iter()
+            .map(|ext| Self::key_path(name, ext))
+            .find(|path| path.exists())
+    }
+
+    /// Downloads the key of a source into the keyring directory. apt only
+    /// reads armored keys from `.asc` files and binary ones from `.gpg` files
+    /// so which one it is decides the name, not the url it came from.
+    fn fetch_key(name: &str, url: &str) -> Result<PathBuf> {
+        let mut curl = Command::new("curl");
+        curl.arg("-fsSL").arg(url);
+        let out = crate::cmd::raw_output(&mut curl)?;
+        if !out.status.success() {
+            return Err(YumaError::command(&curl, out.status, &out.stderr).into());
+        }
+        let ext = if out.stdout.starts_with(b"-----BEGIN PGP") {
+            "asc"
+        } else {
+            "gpg"
+        };
+        let path = Self::key_path(name, ext);
+
+        super::status(
+            super::elevated("install")
+                .arg("-d")
+                .arg("-m")
+                .arg("0755")
+                .arg(KEYRING_DIR),
+        )?;
+        // dd instead of tee so a binary key is not echoed back
+        crate::cmd::output_with_stdin(
+            super::elevated("dd")
+                .arg(format!("of={}", path.display()))
+                .arg("status=none"),
+            &out.stdout,
+        )?;
+        Ok(path)
+    }
+
+    /// The `deb` line of a source, like
+    /// `deb [signed-by=/etc/apt/keyrings/yuma-docker.asc] https://download.docker.com/linux/debian bookworm stable`.
+    fn source_line(repo: &Repo, key: Option<&Path>) -> Result<String> {
+        let url = repo
+            .url
+            .as_ref()
+            .ok_or_else(|| eyre!("The {} source needs a url", repo.name))?;
+        let suite = repo
+            .options
+            .get("suite")
+            .ok_or_else(|| eyre!("The {} source needs a suite option", repo.name))?;
+        let components = repo
+            .options
+            .get("components")
+            .map_or("main", String::as_str);
+
+        let mut options = vec![];
+        if let Some(arch) = repo.options.get("arch") {
+            options.push(format!("arch={arch}"));
+        }
+        if let Some(key) = key {
+            options.push(format!("signed-by={}", key.display()));
+        }
+
+        let mut line = "deb ".to_string();
+        if !options.is_empty() {
+            line.push_str(&format!("[{}] ", options.join(" ")));
+        }
+        line.push_str(&format!("{url} {suite} {components}\n"));
+        Ok(line)
+    }
+}
+
+impl PackageBackend for AptPackager {
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("apt-mark").arg("showmanual"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(
+            Command::new("dpkg-query")
+                .arg("--show")
+                .arg("--showformat=${Package}\t${db:Status-Abbrev}\n"),
+        )?;
+        Ok(stdout
+            .lines()
+            // only count packages that are fully installed, removed packages
+            // can linger with their config files
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(_, status)| status.starts_with("ii"))
+            .map(|(name, _)| name.to_string())
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        // sudo resets the environment so the frontend is passed through `env`
+        super::status(
+            super::elevated("env")
+                .arg("DEBIAN_FRONTEND=noninteractive")
+                .arg("apt-get")
+                .arg("install")
+                .arg("--yes")
+                .args(pkgs),
+        )
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            super::elevated("env")
+                .arg("DEBIAN_FRONTEND=noninteractive")
+                .arg("apt-get")
+                .arg("remove")
+                .arg("--yes")
+                .args(pkgs),
+        )
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        let stdout = super::output(Command::new("dpkg-query").arg("--show").arg(
+            "--showformat=${Package}\t${db:Status-Abbrev}\t${Version}\t${Installed-Size}\t${binary:Summary}\n",
+        ))?;
+        let manual: HashSet<String> = self.list_leaves()?.into_iter().collect();
+
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let name = fields.next()?;
+                if !fields.next()?.starts_with("ii") {
+                    return None;
+                }
+                let version = fields.next()?;
+                let reason = if manual.contains(name) {
+                    InstallReason::Explicit
+                } else {
+                    InstallReason::Dependency
+                };
+                Some(PackageInfo {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    // dpkg counts in KiB
+                    installed_size: fields.next()?.parse::<u64>().ok().map(|kib| kib * 1024),
+                    description: fields
+                        .next()
+                        .filter(|d| !d.is_empty())
+                        .map(ToString::to_string),
+                    repository: None,
+                    reason: Some(reason),
+                })
+            })
+            .collect())
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        // looks like `ripgrep - Recursively searches directories for a regex pattern`
+        let stdout = super::output(Command::new("apt-cache").arg("search").arg(query))?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.split_once(" - "))
+            .map(|(name, desc)| PackageInfo {
+                name: name.to_string(),
+                version: String::new(),
+                description: Some(desc.to_string()),
+                repository: None,
+                installed_size: None,
+                reason: None,
+            })
+            .collect())
+    }
+
+    fn install_version(&self, pkg: SpecficName, version: &str) -> Result<SpecficName> {
+        // looks like ` nodejs | 20.11.1-1nodesource1 | https://deb.nodesource.com/node_20.x nodistro/main amd64 Packages`
+        // with the newest version first
+        let stdout = super::output(Command::new("apt-cache").arg("madison").arg(&pkg))?;
+        let available: Vec<&str> = stdout
+            .lines()
+            .filter_map(|line| line.split('|').nth(1))
+            .map(str::trim)
+            .collect();
+        let Some(exact) = available.iter().find(|v| version_matches(version, v)) else {
+            bail!("No version of {pkg} matches {version}, found {available:?}");
+        };
+
+        super::status(
+            super::elevated("env")
+                .arg("DEBIAN_FRONTEND=noninteractive")
+                .arg("apt-get")
+                .arg("install")
+                .arg("--yes")
+                .arg("--allow-downgrades")
+                .arg(format!("{pkg}={exact}")),
+        )?;
+        Ok(pkg)
+    }
+
+    fn hold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        super::status(super::elevated("apt-mark").arg("hold").args(pkgs))
+    }
+
+    fn unhold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        super::status(super::elevated("apt-mark").arg("unhold").args(pkgs))
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        let stdout = super::output(Command::new("apt-mark").arg("showhold"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        // looks like `curl/stable 7.88.1-10+deb12u6 amd64 [upgradable from: 7.88.1-10+deb12u5]`
+        let stdout = super::output(Command::new("apt").arg("list").arg("--upgradable"))?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let (name, rest) = line.split_once('/')?;
+                let new = rest.split_whitespace().nth(1)?;
+                let old = rest
+                    .split_once("upgradable from: ")?
+                    .1
+                    .trim_end_matches(']');
+                Some(Upgrade {
+                    name: name.to_string(),
+                    old: old.to_string(),
+                    new: new.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn upgrade(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        if pkgs.is_empty() {
+            return Ok(());
+        }
+        super::status(
+            super::elevated("env")
+                .arg("DEBIAN_FRONTEND=noninteractive")
+                .arg("apt-get")
+                .arg("install")
+                .arg("--only-upgrade")
+                .arg("--yes")
+                .args(pkgs),
+        )
+    }
+
+    fn list_repos(&self) -> Result<Vec<String>> {
+        let entries = match fs::read_dir(SOURCES_DIR) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names = vec![];
+        for entry in entries {
+            let file = entry?.file_name();
+            let name = file
+                .to_str()
+                .and_then(|f| f.strip_prefix(SOURCE_PREFIX))
+                .and_then(|f| f.strip_suffix(".list"));
+            names.extend(name.map(ToString::to_string));
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn add_repo(&self, repo: &Repo) -> Result<()> {
+        // catches a missing url or suite before anything is downloaded
+        Self::source_line(repo, None)?;
+        let path = Self::source_path(&repo.name);
+        let existing = Self::existing_key(&repo.name);
+        if repo.key.is_none() || existing.is_some() {
+            let line = Self::source_line(repo, repo.key.as_ref().and(existing.as_deref()))?;
+            if fs::read_to_string(&path).is_ok_and(|old| old == line) {
+                return Ok(());
+            }
+        }
+
+        let key = match repo.key.as_ref() {
+            Some(url) => Some(Self::fetch_key(&repo.name, url)?),
+            None => None,
+        };
+        let line = Self::source_line(repo, key.as_deref())?;
+        crate::cmd::output_with_stdin(super::elevated("tee").arg(&path), line.as_bytes())?;
+        super::status(super::elevated("apt-get").arg("update"))
+    }
+
+    fn remove_repos(&self, names: Vec<String>) -> Result<()> {
+        let mut files = vec![];
+        for name in names.iter() {
+            files.push(Self::source_path(name));
+            files.push(Self::key_path(name, "asc"));
+            files.push(Self::key_path(name, "gpg"));
+        }
+        super::status(super::elevated("rm").arg("-f").args(files))?;
+        super::status(super::elevated("apt-get").arg("update"))
+    }
+
+    fn list_orphans(&self) -> Result<Vec<SpecficName>> {
+        let stdout = super::output(Command::new("apt-get").arg("autoremove").arg("--simulate"))?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("Remv "))
+            .filter_map(|rest| rest.split_whitespace().next())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn removal_plan(&self, pkgs: &[SpecficName]) -> Result<Vec<SpecficName>> {
+        // looks like `Remv libfoo1 [1.2-3]` for everything that would go
+        let stdout = super::output(
+            Command::new("apt-get")
+                .arg("remove")
+                .arg("--simulate")
+                .args(pkgs),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("Remv "))
+            .filter_map(|rest| rest.split_whitespace().next())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn protected(&self) -> Vec<SpecficName> {
+        super::protect::patterns(&[
+            "base-files",
+            "linux-image-*",
+            "grub-*",
+            "systemd",
+            "libc6",
+            "sudo",
+            "apt",
+            "dpkg",
+        ])
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -1,51 +1,344 @@
+use crate::cmd;
 use crate::prelude::*;
 
-use std::process::Command;
+use std::{
+    collections::BTreeSet,
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
-use super::PackageBackend;
+use color_eyre::eyre::ensure;
+
+use super::{InstallReason, PackageBackend, PackageInfo, Repo, SpecficName, Upgrade};
+
+/// The parts of `brew info --json=v2` we care about.
+#[derive(Debug, Deserialize)]
+struct BrewInfo {
+    #[serde(default)]
+    formulae: Vec<Formula>,
+    #[serde(default)]
+    casks: Vec<Cask>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Formula {
+    name: String,
+    tap: Option<String>,
+    desc: Option<String>,
+    versions: FormulaVersions,
+    #[serde(default)]
+    installed: Vec<InstalledFormula>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FormulaVersions {
+    stable: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstalledFormula {
+    version: String,
+    #[serde(default)]
+    installed_on_request: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cask {
+    token: String,
+    tap: Option<String>,
+    desc: Option<String>,
+    version: String,
+    /// The installed version.
+    installed: Option<String>,
+}
+
+impl From<Formula> for PackageInfo {
+    fn from(f: Formula) -> Self {
+        let installed = f.installed.first();
+        PackageInfo {
+            version: installed
+                .map(|i| i.version.clone())
+                .or(f.versions.stable)
+                .unwrap_or_default(),
+            reason: installed.map(|i| {
+                if i.installed_on_request {
+                    InstallReason::Explicit
+                } else {
+                    InstallReason::Dependency
+                }
+            }),
+            name: f.name,
+            description: f.desc,
+            repository: f.tap,
+            installed_size: None,
+        }
+    }
+}
+
+impl From<Cask> for PackageInfo {
+    fn from(c: Cask) -> Self {
+        PackageInfo {
+            // casks can't be dependencies
+            reason: c.installed.is_some().then_some(InstallReason::Explicit),
+            version: c.installed.unwrap_or(c.version),
+            name: c.token,
+            description: c.desc,
+            repository: c.tap,
+            installed_size: None,
+        }
+    }
+}
+
+/// The parts of `brew outdated --json=v2` we care about.
+#[derive(Debug, Deserialize)]
+struct BrewOutdated {
+    #[serde(default)]
+    formulae: Vec<Outdated>,
+    #[serde(default)]
+    casks: Vec<Outdated>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Outdated {
+    name: String,
+    installed_versions: Vec<String>,
+    current_version: String,
+}
+
+impl BrewInfo {
+    fn into_infos(self) -> Vec<PackageInfo> {
+        let formulae = self.formulae.into_iter().map(PackageInfo::from);
+        let casks = self.casks.into_iter().map(PackageInfo::from);
+        formulae.chain(casks).collect()
+    }
+}
 
 #[derive(Debug, Default)]
-pub struct BrewPackager;
+pub struct BrewPackager {
+    state: Option<PathBuf>,
+}
 
-impl PackageBackend for BrewPackager {
-    fn list_leaves(&self) -> Vec<String> {
-        let stdout = Command::new("brew").arg("leaves").output().unwrap().stdout;
-        String::from_utf8(stdout)
-            .unwrap()
-            .lines()
-            .map(ToString::to_string)
-            .collect()
+impl BrewPackager {
+    /// Creates a packager that keeps track of the taps it added in the given
+    /// file instead of `~/.local/share/yuma/brew-taps.json`.
+    pub fn with_state(state: impl Into<PathBuf>) -> Self {
+        Self {
+            state: Some(state.into()),
+        }
     }
 
-    fn list_installed(&self) -> Vec<String> {
-        let stdout = Command::new("brew").arg("list").output().unwrap().stdout;
-        String::from_utf8(stdout)
-            .unwrap()
-            .lines()
-            .map(ToString::to_string)
-            .collect()
+    fn state(&self) -> PathBuf {
+        self.state.clone().unwrap_or_else(|| {
+            let home = env::var_os("HOME").unwrap_or_default();
+            Path::new(&home).join(".local/share/yuma/brew-taps.json")
+        })
     }
 
-    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
-        Command::new("brew")
-            .arg("install")
-            .args(pkgs)
-            .spawn()?
-            .wait()?;
+    /// The taps that were added through a [`Repo`]. Anything tapped by hand
+    /// isn't in here so it is never untapped.
+    fn added_taps(&self) -> Result<BTreeSet<String>> {
+        let path = self.state();
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+        Ok(json::from_str(&fs::read_to_string(path)?)?)
+    }
 
+    fn set_added_taps(&self, taps: &BTreeSet<String>) -> Result<()> {
+        let path = self.state();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, json::to_string_pretty(taps)?)?;
         Ok(())
     }
 
+    fn taps(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("brew").arg("tap"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+}
+
+impl PackageBackend for BrewPackager {
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("brew").arg("leaves"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("brew").arg("list"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(Command::new("brew").arg("install").args(pkgs))
+    }
+
     fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
-        Command::new("brew")
-            .arg("remove")
-            .args(pkgs)
-            .spawn()?
-            .wait()?;
+        super::status(Command::new("brew").arg("remove").args(pkgs))
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        let stdout = super::output(
+            Command::new("brew")
+                .arg("info")
+                .arg("--json=v2")
+                .arg("--installed"),
+        )?;
+        let info: BrewInfo = json::from_str(&stdout)?;
+        Ok(info.into_infos())
+    }
+
+    fn info(&self, name: &str) -> Result<Option<PackageInfo>> {
+        let mut info_cmd = Command::new("brew");
+        info_cmd.arg("info").arg("--json=v2").arg(name);
+        let out = cmd::raw_output(&mut info_cmd)?;
+        if !out.status.success() {
+            if String::from_utf8_lossy(&out.stderr).contains("No available") {
+                return Ok(None);
+            }
+            return Err(YumaError::command(&info_cmd, out.status, &out.stderr).into());
+        }
+
+        let info: BrewInfo = json::from_slice(&out.stdout)?;
+        Ok(info.into_infos().pop())
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        // searching descriptions needs every formula to be evaluated
+        let mut search_cmd = Command::new("brew");
+        search_cmd
+            .arg("search")
+            .arg("--desc")
+            .arg("--eval-all")
+            .arg(query);
+        let out = cmd::raw_output(&mut search_cmd)?;
+        if !out.status.success() {
+            // exits with 1 when nothing matches
+            if String::from_utf8_lossy(&out.stderr).contains("No formulae or casks found") {
+                return Ok(vec![]);
+            }
+            return Err(YumaError::command(&search_cmd, out.status, &out.stderr).into());
+        }
+
+        // looks like `jq: Lightweight and flexible command-line JSON processor`
+        // under `==> Formulae` and `==> Casks` headings
+        Ok(String::from_utf8(out.stdout)?
+            .lines()
+            .filter(|line| !line.starts_with("==>"))
+            .filter_map(|line| line.split_once(": "))
+            .map(|(name, desc)| PackageInfo {
+                name: name.trim().to_string(),
+                version: String::new(),
+                description: Some(desc.trim().to_string()),
+                repository: None,
+                installed_size: None,
+                reason: None,
+            })
+            .collect())
+    }
+
+    /// Brew keeps older versions around as separate formulae so `node` at
+    /// `20` is installed as `node@20`.
+    fn pinned_name(&self, pkg: &str, version: &str) -> Result<SpecficName> {
+        // only some versions have a formula, like `node@20` or `python@3.12`
+        let versioned = format!("{pkg}@{version}");
+        ensure!(
+            self.info(&versioned)?.is_some(),
+            "There is no {versioned} formula, pin {pkg} to a version brew has a formula for"
+        );
+        Ok(versioned)
+    }
+
+    fn install_version(&self, pkg: SpecficName, version: &str) -> Result<SpecficName> {
+        let versioned = self.pinned_name(&pkg, version)?;
+        self.install(vec![versioned.clone()])?;
+        Ok(versioned)
+    }
+
+    fn hold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        super::status(Command::new("brew").arg("pin").args(pkgs))
+    }
+
+    fn unhold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        super::status(Command::new("brew").arg("unpin").args(pkgs))
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        let stdout = super::output(Command::new("brew").arg("list").arg("--pinned"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        let stdout = super::output(Command::new("brew").arg("outdated").arg("--json=v2"))?;
+        let outdated: BrewOutdated = json::from_str(&stdout)?;
+        Ok(outdated
+            .formulae
+            .into_iter()
+            .chain(outdated.casks)
+            .map(|o| Upgrade {
+                name: o.name,
+                old: o.installed_versions.last().cloned().unwrap_or_default(),
+                new: o.current_version,
+            })
+            .collect())
+    }
+
+    fn upgrade(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        if pkgs.is_empty() {
+            return Ok(());
+        }
+        super::status(Command::new("brew").arg("upgrade").args(pkgs))
+    }
+
+    fn list_repos(&self) -> Result<Vec<String>> {
+        // taps that were untapped by hand since are no longer ours
+        let added = self.added_taps()?;
+        Ok(self
+            .taps()?
+            .into_iter()
+            .filter(|tap| added.contains(tap))
+            .collect())
+    }
+
+    fn add_repo(&self, repo: &Repo) -> Result<()> {
+        if !self.taps()?.contains(&repo.name) {
+            super::status(
+                Command::new("brew")
+                    .arg("tap")
+                    .arg(&repo.name)
+                    .args(repo.url.as_ref()),
+            )?;
+        }
+
+        // a declared tap is managed from now on, even if it was there before
+        let mut added = self.added_taps()?;
+        if added.insert(repo.name.clone()) {
+            self.set_added_taps(&added)?;
+        }
         Ok(())
     }
 
-    fn resolve_name(&self, _name: super::GenericName) -> super::SpecficName {
-        todo!()
+    fn remove_repos(&self, names: Vec<String>) -> Result<()> {
+        super::status(Command::new("brew").arg("untap").args(names.iter()))?;
+        let mut added = self.added_taps()?;
+        added.retain(|tap| !names.contains(tap));
+        self.set_added_taps(&added)
+    }
+
+    fn list_orphans(&self) -> Result<Vec<SpecficName>> {
+        // looks like `==> Would autoremove 2 unneeded formulae:` followed by
+        // a name on each line
+        let stdout = super::output(Command::new("brew").arg("autoremove").arg("--dry-run"))?;
+        Ok(stdout
+            .lines()
+            .filter(|line| !line.starts_with("==>") && !line.is_empty())
+            .map(|line| line.trim().to_string())
+            .collect())
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
     }
 }
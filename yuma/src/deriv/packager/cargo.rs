@@ -1,28 +1,112 @@
 use crate::prelude::*;
 
+use std::{collections::HashMap, env, path::PathBuf, process::Command};
+
 pub use super::PackageBackend;
+use super::PackageInfo;
 
+/// Installs crates with `cargo install`. Cargo keeps track of everything it
+/// installed in `$CARGO_HOME/.crates2.json` which is where we read state from.
 #[derive(Debug, Default)]
-pub struct CargoPackager;
+pub struct CargoPackager {
+    home: Option<PathBuf>,
+}
+
+/// The parts of `.crates2.json` we care about. Keys look like
+/// `ripgrep 14.1.0 (registry+https://github.com/rust-lang/crates.io-index)`.
+#[derive(Debug, Deserialize)]
+struct CratesManifest {
+    installs: HashMap<String, json::Value>,
+}
+
+impl CargoPackager {
+    /// Creates a packager that uses the given directory as `CARGO_HOME`
+    /// instead of looking it up from the environment.
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        Self {
+            home: Some(home.into()),
+        }
+    }
+
+    fn home(&self) -> PathBuf {
+        if let Some(home) = &self.home {
+            return home.clone();
+        }
+
+        env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".cargo")
+            })
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new("cargo");
+        cmd.env("CARGO_HOME", self.home());
+        cmd
+    }
+}
 
 impl PackageBackend for CargoPackager {
-    fn list_installed(&self) -> Vec<String> {
-        todo!()
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let path = self.home().join(".crates2.json");
+
+        // nothing has ever been installed
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        let f = fs::File::open(path)?;
+        let manifest: CratesManifest = json::from_reader(f)?;
+        Ok(manifest
+            .installs
+            .keys()
+            .filter_map(|key| key.split_whitespace().next())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        // cargo only ever installs what it was asked to
+        self.list_installed()
     }
 
-    fn list_leaves(&self) -> Vec<String> {
-        todo!()
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(self.command().arg("install").arg("--locked").args(pkgs))
     }
 
-    fn install(&self, _pkgs: Vec<super::SpecficName>) -> Result<()> {
-        todo!()
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(self.command().arg("uninstall").args(pkgs))
     }
 
-    fn remove(&self, _pkgs: Vec<super::SpecficName>) -> Result<()> {
-        todo!()
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        // looks like `ripgrep = "14.1.0"    # ripgrep is a line-oriented search tool`
+        let stdout = super::output(
+            self.command()
+                .arg("search")
+                .arg("--limit")
+                .arg("20")
+                .arg(query),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let (name, rest) = line.split_once(" = ")?;
+                let (version, desc) = rest.split_once('#').unwrap_or((rest, ""));
+                let desc = desc.trim();
+                Some(PackageInfo {
+                    name: name.to_string(),
+                    version: version.trim().trim_matches('"').to_string(),
+                    description: (!desc.is_empty()).then(|| desc.to_string()),
+                    repository: Some("crates.io".into()),
+                    installed_size: None,
+                    reason: None,
+                })
+            })
+            .collect())
     }
 
-    fn resolve_name(&self, _name: super::GenericName) -> super::SpecficName {
-        todo!()
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
     }
 }
@@ -0,0 +1,158 @@
+use crate::prelude::*;
+
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use color_eyre::eyre::{bail, eyre};
+
+use super::{PackageBackend, PackageInfo, PackagerType, Repo, SpecficName, Upgrade};
+
+thread_local! {
+static CUSTOM: RefCell<HashMap<String, Arc<dyn PackageBackend>>> = RefCell::default();
+}
+
+/// Makes a backend available as `name`. Registering the same name again
+/// replaces the old backend.
+///
+/// Names of the builtin packagers are taken and can't be registered.
+pub fn register(name: impl Into<String>, backend: Arc<dyn PackageBackend>) -> Result<()> {
+    let name = name.into();
+    if PackagerType::builtin(&name).is_some() {
+        bail!("{name} is already a builtin packager");
+    }
+    CUSTOM.with(|c| c.borrow_mut().insert(name, backend));
+    Ok(())
+}
+
+/// The names of every registered backend.
+pub fn registered() -> Vec<String> {
+    let mut names: Vec<String> = CUSTOM.with(|c| c.borrow().keys().cloned().collect());
+    names.sort();
+    names
+}
+
+/// Whether a backend was registered as `name`.
+pub fn is_registered(name: &str) -> bool {
+    CUSTOM.with(|c| c.borrow().contains_key(name))
+}
+
+fn lookup(name: &str) -> Result<Arc<dyn PackageBackend>> {
+    CUSTOM
+        .with(|c| c.borrow().get(name).cloned())
+        .ok_or_else(|| eyre!("No packager registered as {name}"))
+}
+
+/// Forwards to the backend registered under its name. The lookup happens on
+/// every call so a packager can be named (say by loading a cache) before its
+/// backend is registered.
+#[derive(Debug)]
+pub struct CustomPackager {
+    name: String,
+}
+
+impl CustomPackager {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl PackageBackend for CustomPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        lookup(&self.name)?.list_installed()
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        lookup(&self.name)?.list_leaves()
+    }
+
+    fn install(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.install(pkgs)
+    }
+
+    fn remove(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.remove(pkgs)
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        lookup(&self.name)?.installed_info()
+    }
+
+    fn info(&self, name: &str) -> Result<Option<PackageInfo>> {
+        lookup(&self.name)?.info(name)
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        lookup(&self.name)?.search(query)
+    }
+
+    fn split_version(&self, name: SpecficName) -> (SpecficName, Option<String>) {
+        match lookup(&self.name) {
+            Ok(backend) => backend.split_version(name),
+            Err(_) => (name, None),
+        }
+    }
+
+    fn pinned_name(&self, pkg: &str, version: &str) -> Result<SpecficName> {
+        lookup(&self.name)?.pinned_name(pkg, version)
+    }
+
+    fn install_version(&self, pkg: SpecficName, version: &str) -> Result<SpecficName> {
+        lookup(&self.name)?.install_version(pkg, version)
+    }
+
+    fn hold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.hold(pkgs)
+    }
+
+    fn unhold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.unhold(pkgs)
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        lookup(&self.name)?.list_held()
+    }
+
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        lookup(&self.name)?.list_upgrades()
+    }
+
+    fn upgrade(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.upgrade(pkgs)
+    }
+
+    fn list_repos(&self) -> Result<Vec<String>> {
+        lookup(&self.name)?.list_repos()
+    }
+
+    fn add_repo(&self, repo: &Repo) -> Result<()> {
+        lookup(&self.name)?.add_repo(repo)
+    }
+
+    fn remove_repos(&self, names: Vec<String>) -> Result<()> {
+        lookup(&self.name)?.remove_repos(names)
+    }
+
+    fn list_orphans(&self) -> Result<Vec<SpecficName>> {
+        lookup(&self.name)?.list_orphans()
+    }
+
+    fn remove_orphans(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        lookup(&self.name)?.remove_orphans(pkgs)
+    }
+
+    fn removal_plan(&self, pkgs: &[SpecficName]) -> Result<Vec<SpecficName>> {
+        lookup(&self.name)?.removal_plan(pkgs)
+    }
+
+    fn protected(&self) -> Vec<SpecficName> {
+        lookup(&self.name)
+            .map(|backend| backend.protected())
+            .unwrap_or_default()
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> SpecficName {
+        match lookup(&self.name) {
+            Ok(backend) => backend.resolve_name(name),
+            Err(_) => name.0,
+        }
+    }
+}
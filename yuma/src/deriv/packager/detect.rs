@@ -0,0 +1,233 @@
+//! Figures out which package manager a system uses. In order of precedence:
+//!
+//! 1. the [`PACKAGER_ENV`] environment variable,
+//! 2. a packager set through [`YumaCtx::default_packager`],
+//! 3. the `ID` and `ID_LIKE` fields of `/etc/os-release`,
+//! 4. the first known package manager found on `PATH`.
+//!
+//! [`YumaCtx::default_packager`]: crate::ctx::YumaCtx::default_packager
+
+use crate::prelude::*;
+
+use std::{
+    cell::RefCell,
+    env,
+    ffi::OsString,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+
+use super::{PackageBackend, PackagerType};
+
+/// Set this to the name of a packager to skip detection.
+pub const PACKAGER_ENV: &str = "YUMA_PACKAGER";
+
+/// Package managers in the order they are probed for on `PATH`, along with
+/// the program that gives them away.
+const PROBES: &[(&str, PackagerType)] = &[
+    ("paru", PackagerType::Paru),
+    ("apt-get", PackagerType::Apt),
+    ("dnf", PackagerType::Dnf),
+    ("apk", PackagerType::Apk),
+    ("xbps-install", PackagerType::Xbps),
+    ("brew", PackagerType::Brew),
+    ("nix", PackagerType::Nix),
+];
+
+thread_local! {
+static DETECTED: RefCell<Option<std::result::Result<PackagerType, String>>> =
+    const { RefCell::new(None) };
+}
+
+/// Detection for the running system, done once per thread.
+pub(crate) fn detected() -> std::result::Result<PackagerType, String> {
+    DETECTED.with(|d| {
+        d.borrow_mut()
+            .get_or_insert_with(|| Detector::new().detect().map_err(|e| format!("{e:#}")))
+            .clone()
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct Detector {
+    root: PathBuf,
+    path: OsString,
+    requested: Option<String>,
+    preferred: Option<PackagerType>,
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            path: env::var_os("PATH").unwrap_or_default(),
+            requested: env::var(PACKAGER_ENV).ok().filter(|s| !s.is_empty()),
+            preferred: None,
+        }
+    }
+}
+
+impl Detector {
+    /// A detector for the running system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks for `etc/os-release` and everything on `PATH` under `root`
+    /// instead of `/`.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Overrides the `PATH` that is probed for package managers.
+    pub fn path(mut self, path: impl Into<OsString>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Overrides the value of [`PACKAGER_ENV`].
+    pub fn requested(mut self, name: Option<&str>) -> Self {
+        self.requested = name.map(ToOwned::to_owned);
+        self
+    }
+
+    /// A packager to use instead of detecting one, unless [`PACKAGER_ENV`]
+    /// is set.
+    pub fn preferred(mut self, ptype: Option<PackagerType>) -> Self {
+        self.preferred = ptype;
+        self
+    }
+
+    pub fn detect(&self) -> Result<PackagerType> {
+        if let Some(name) = self.requested.as_deref() {
+            return PackagerType::try_from(name)
+                .wrap_err_with(|| format!("Invalid value for {PACKAGER_ENV}"));
+        }
+
+        if let Some(ptype) = self.preferred.clone() {
+            return Ok(ptype);
+        }
+
+        // the distro tells us what it should have but the packager we use
+        // for it (like paru on arch) might still be missing
+        let from_release = self
+            .os_release()
+            .iter()
+            .filter_map(|id| distro_packager(id))
+            .find(|ptype| self.probe(ptype));
+        if let Some(ptype) = from_release {
+            return Ok(ptype);
+        }
+
+        PROBES
+            .iter()
+            .map(|(_, ptype)| ptype)
+            .find(|ptype| self.probe(ptype))
+            .cloned()
+            .ok_or_else(|| {
+                eyre!(
+                    "Could not detect a package manager, set {PACKAGER_ENV} to pick one explicitly"
+                )
+            })
+    }
+
+    /// The `ID` followed by the `ID_LIKE` entries of the os-release file.
+    fn os_release(&self) -> Vec<String> {
+        let etc = self.root.join("etc/os-release");
+        let Ok(contents) = fs::read_to_string(etc)
+            .or_else(|_| fs::read_to_string(self.root.join("usr/lib/os-release")))
+        else {
+            return vec![];
+        };
+
+        let field = |key: &str| {
+            contents
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(k, _)| k.trim() == key)
+                .map(|(_, v)| v.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+                .unwrap_or_default()
+        };
+
+        let mut ids = vec![field("ID")];
+        ids.extend(field("ID_LIKE").split_whitespace().map(ToOwned::to_owned));
+        ids.retain(|id| !id.is_empty());
+        ids
+    }
+
+    /// Whether the program for a packager is on `PATH`.
+    fn probe(&self, ptype: &PackagerType) -> bool {
+        PROBES
+            .iter()
+            .find(|(_, p)| p == ptype)
+            .is_some_and(|(program, _)| self.has_program(program))
+    }
+
+    /// Whether an executable called `program` is on `PATH`.
+    pub fn has_program(&self, program: &str) -> bool {
+        env::split_paths(&self.path).any(|dir| {
+            let dir = dir.strip_prefix("/").unwrap_or(&dir);
+            is_executable(&self.root.join(dir).join(program))
+        })
+    }
+}
+
+fn distro_packager(id: &str) -> Option<PackagerType> {
+    Some(match id {
+        "arch" | "archarm" | "manjaro" | "endeavouros" | "garuda" | "artix" => PackagerType::Paru,
+        "debian" | "ubuntu" | "linuxmint" | "pop" | "raspbian" | "elementary" => PackagerType::Apt,
+        "fedora" | "rhel" | "centos" | "rocky" | "almalinux" | "amzn" => PackagerType::Dnf,
+        "alpine" => PackagerType::Apk,
+        "void" => PackagerType::Xbps,
+        "nixos" => PackagerType::Nix,
+        _ => return None,
+    })
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Stands in for a packager when detection failed. Everything but name
+/// resolution returns the reason detection failed.
+#[derive(Debug)]
+pub struct UndetectedPackager {
+    reason: String,
+}
+
+impl UndetectedPackager {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+
+    fn err<T>(&self) -> Result<T> {
+        Err(eyre!("{}", self.reason))
+    }
+}
+
+impl PackageBackend for UndetectedPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        self.err()
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.err()
+    }
+
+    fn install(&self, _pkgs: Vec<super::SpecficName>) -> Result<()> {
+        self.err()
+    }
+
+    fn remove(&self, _pkgs: Vec<super::SpecficName>) -> Result<()> {
+        self.err()
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,139 @@
+use crate::prelude::*;
+
+use std::process::Command;
+
+use super::{PackageBackend, PackageInfo};
+
+#[derive(Debug, Default)]
+pub struct DnfPackager;
+
+impl PackageBackend for DnfPackager {
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let stdout = super::output(
+            Command::new("dnf")
+                .arg("repoquery")
+                .arg("--userinstalled")
+                .arg("--queryformat=%{name}\n"),
+        )?;
+        Ok(stdout
+            .lines()
+            // dnf4 adds its own line endings so every other line is empty
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(
+            Command::new("rpm")
+                .arg("--query")
+                .arg("--all")
+                .arg("--queryformat=%{NAME}\n"),
+        )?;
+        Ok(stdout
+            .lines()
+            // imported signing keys show up as packages
+            .filter(|name| *name != "gpg-pubkey")
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            super::elevated("dnf")
+                .arg("install")
+                .arg("--assumeyes")
+                .args(pkgs),
+        )
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            super::elevated("dnf")
+                .arg("remove")
+                .arg("--assumeyes")
+                .args(pkgs),
+        )
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        // dnf4 prints `ripgrep.x86_64 : Line oriented search tool` under
+        // `=== Name Matched ===` headings while dnf5 separates them with a tab
+        let stdout = super::output(Command::new("dnf").arg("search").arg("--quiet").arg(query))?;
+        Ok(stdout
+            .lines()
+            .filter(|line| !line.starts_with('='))
+            .filter_map(|line| line.split_once(" : ").or_else(|| line.split_once('\t')))
+            .map(|(name, desc)| {
+                let name = name.trim();
+                let name = name.rsplit_once('.').map_or(name, |(name, _arch)| name);
+                PackageInfo {
+                    name: name.to_string(),
+                    version: String::new(),
+                    description: Some(desc.trim().to_string()),
+                    repository: None,
+                    installed_size: None,
+                    reason: None,
+                }
+            })
+            .collect())
+    }
+
+    fn list_orphans(&self) -> Result<Vec<super::SpecficName>> {
+        let stdout = super::output(
+            Command::new("dnf")
+                .arg("repoquery")
+                .arg("--quiet")
+                .arg("--unneeded")
+                .arg("--queryformat")
+                .arg("%{name}\n"),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn removal_plan(&self, pkgs: &[super::SpecficName]) -> Result<Vec<super::SpecficName>> {
+        let mut cmd = super::elevated("dnf");
+        cmd.env("LC_ALL", "C")
+            .arg("remove")
+            .arg("--assumeno")
+            .args(pkgs);
+        // answering no makes dnf exit with an error even though the plan was
+        // printed just fine
+        let out = crate::cmd::raw_output(&mut cmd)?;
+        let plan = parse_removals(&String::from_utf8(out.stdout)?);
+        if plan.is_empty() && !out.status.success() {
+            return Err(YumaError::command(&cmd, out.status, &out.stderr).into());
+        }
+        Ok(plan)
+    }
+
+    fn protected(&self) -> Vec<super::SpecficName> {
+        super::protect::patterns(&[
+            "kernel*", "grub2-*", "shim-*", "systemd", "glibc", "sudo", "dnf", "rpm",
+        ])
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
+
+/// Picks the packages out of the `Removing:`, `Removing unused dependencies:`
+/// and `Removing dependent packages:` sections of a transaction, where rows
+/// look like ` htop   x86_64   3.2.2-1.fc38   @updates   420 k`.
+fn parse_removals(stdout: &str) -> Vec<super::SpecficName> {
+    let mut removing = false;
+    let mut plan = vec![];
+    for line in stdout.lines() {
+        if !line.starts_with(' ') {
+            removing = line.starts_with("Removing") && line.ends_with(':');
+        } else if removing {
+            plan.extend(line.split_whitespace().next().map(ToString::to_string));
+        }
+    }
+    plan
+}
@@ -5,12 +5,12 @@ use crate::prelude::*;
 pub struct FakePackager;
 
 impl PackageBackend for FakePackager {
-    fn list_installed(&self) -> Vec<String> {
-        vec![]
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(vec![])
     }
 
-    fn list_leaves(&self) -> Vec<String> {
-        vec![]
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(vec![])
     }
 
     fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
@@ -23,6 +23,42 @@ impl PackageBackend for FakePackager {
         Ok(())
     }
 
+    fn install_version(
+        &self,
+        pkg: super::SpecficName,
+        version: &str,
+    ) -> Result<super::SpecficName> {
+        crate::log::info!("Would have installed: {} {}", pkg, version);
+        Ok(pkg)
+    }
+
+    fn hold(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        crate::log::info!("Would have held: {:?}", pkgs);
+        Ok(())
+    }
+
+    fn unhold(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        crate::log::info!("Would have unheld: {:?}", pkgs);
+        Ok(())
+    }
+
+    fn list_held(&self) -> Result<Vec<super::SpecficName>> {
+        Ok(vec![])
+    }
+
+    fn list_upgrades(&self) -> Result<Vec<super::Upgrade>> {
+        Ok(vec![])
+    }
+
+    fn upgrade(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        crate::log::info!("Would have upgraded: {:?}", pkgs);
+        Ok(())
+    }
+
+    fn list_orphans(&self) -> Result<Vec<super::SpecficName>> {
+        Ok(vec![])
+    }
+
     fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
         name.0
     }
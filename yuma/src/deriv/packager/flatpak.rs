@@ -0,0 +1,183 @@
+use crate::prelude::*;
+
+use std::process::Command;
+
+use color_eyre::eyre::{ensure, eyre};
+
+use super::{PackageBackend, Repo};
+
+/// The remote apps are installed from when none is given.
+pub const DEFAULT_REMOTE: &str = "flathub";
+
+/// Marks the remotes that were added through a [`Repo`] so that remotes added
+/// by hand are never removed.
+const MANAGED_COMMENT: &str = "Managed by yuma";
+
+/// Which flatpak installation a set of apps goes into.
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FlatpakScope {
+    /// Per user installation in `~/.local/share/flatpak`.
+    #[default]
+    User,
+    /// System wide installation in `/var/lib/flatpak`.
+    System,
+}
+
+impl FlatpakScope {
+    fn flag(self) -> &'static str {
+        match self {
+            FlatpakScope::User => "--user",
+            FlatpakScope::System => "--system",
+        }
+    }
+}
+
+/// Installs apps from a single flatpak remote into either the user or the
+/// system installation. Leaves are only the apps that came from this remote so
+/// that different remotes can be declared side by side.
+#[derive(Debug)]
+pub struct FlatpakPackager {
+    scope: FlatpakScope,
+    remote: String,
+}
+
+impl Default for FlatpakPackager {
+    fn default() -> Self {
+        Self::new(FlatpakScope::default(), DEFAULT_REMOTE)
+    }
+}
+
+impl FlatpakPackager {
+    pub fn new(scope: FlatpakScope, remote: impl Into<String>) -> Self {
+        Self {
+            scope,
+            remote: remote.into(),
+        }
+    }
+
+    /// Lists the installed apps along with the remote they came from.
+    fn apps(&self) -> Result<Vec<(String, String)>> {
+        let stdout = super::output(
+            Command::new("flatpak")
+                .arg("list")
+                .arg("--app")
+                .arg(self.scope.flag())
+                .arg("--columns=application,origin"),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(app, origin)| (app.to_string(), origin.trim().to_string()))
+            .collect())
+    }
+}
+
+impl PackageBackend for FlatpakPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(self.apps()?.into_iter().map(|(app, _)| app).collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(self
+            .apps()?
+            .into_iter()
+            .filter(|(_, origin)| *origin == self.remote)
+            .map(|(app, _)| app)
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            Command::new("flatpak")
+                .arg("install")
+                .arg(self.scope.flag())
+                .arg("--noninteractive")
+                .arg(&self.remote)
+                .args(pkgs),
+        )
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            Command::new("flatpak")
+                .arg("uninstall")
+                .arg(self.scope.flag())
+                .arg("--noninteractive")
+                .args(pkgs),
+        )
+    }
+
+    fn list_repos(&self) -> Result<Vec<String>> {
+        let stdout = super::output(
+            Command::new("flatpak")
+                .arg("remotes")
+                .arg(self.scope.flag())
+                .arg("--columns=name,comment"),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(name, comment)| *name == self.remote && comment.trim() == MANAGED_COMMENT)
+            .map(|(name, _)| name.to_string())
+            .collect())
+    }
+
+    fn add_repo(&self, repo: &Repo) -> Result<()> {
+        // other remotes get their own packager
+        ensure!(
+            repo.name == self.remote,
+            "This flatpak packager can only add the {} remote",
+            self.remote
+        );
+        let url = repo
+            .url
+            .as_ref()
+            .ok_or_else(|| eyre!("The {} remote needs a url", repo.name))?;
+
+        let remotes = super::output(
+            Command::new("flatpak")
+                .arg("remotes")
+                .arg(self.scope.flag())
+                .arg("--columns=name"),
+        )?;
+        let exists = remotes.lines().any(|name| name.trim() == repo.name);
+
+        let mut cmd = Command::new("flatpak");
+        if exists {
+            // remote-add --if-not-exists would leave a changed url or key alone
+            cmd.arg("remote-modify")
+                .arg(self.scope.flag())
+                .arg(format!("--url={url}"))
+                .arg(format!("--comment={MANAGED_COMMENT}"));
+        } else {
+            cmd.arg("remote-add")
+                .arg(self.scope.flag())
+                .arg("--if-not-exists")
+                .arg(format!("--comment={MANAGED_COMMENT}"));
+        }
+        if let Some(key) = repo.key.as_ref() {
+            cmd.arg(format!("--gpg-import={key}"));
+        }
+        cmd.arg(&repo.name);
+        if !exists {
+            cmd.arg(url);
+        }
+        super::status(&mut cmd)
+    }
+
+    fn remove_repos(&self, names: Vec<String>) -> Result<()> {
+        for name in names {
+            super::status(
+                Command::new("flatpak")
+                    .arg("remote-delete")
+                    .arg(self.scope.flag())
+                    .arg(name),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,204 @@
+use crate::cmd;
+use crate::prelude::*;
+
+use std::{
+    cmp::Ordering,
+    env,
+    path::{Component, Path, PathBuf},
+    process::{self, Command},
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+use color_eyre::eyre::{ensure, WrapErr};
+
+/// Extension of native freight packages.
+pub const EXTENSION: &str = "freight";
+
+/// Name of the manifest at the root of every archive.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Directory in the archive that holds the payload, laid out relative to the
+/// prefix it gets installed into.
+const FILES_DIR: &str = "files";
+
+/// Describes what is in a freight package.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    /// Every file in the package relative to the install prefix.
+    pub files: Vec<PathBuf>,
+    /// Names of other freight packages that need to be installed first.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// A package on disk. The archive is a gzipped tarball that holds a
+/// `manifest.json` and a `files/` directory with the payload.
+#[derive(Debug, Clone)]
+pub struct FreightArchive {
+    pub path: PathBuf,
+    pub manifest: Manifest,
+}
+
+/// A directory that is deleted again once dropped.
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    fn new(what: &str) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+        let dir = env::temp_dir().join(format!("yuma-freight-{what}-{}-{n}", process::id()));
+        fs::create_dir_all(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+impl Manifest {
+    /// Makes sure every file stays inside the prefix it is installed into.
+    fn check_files(&self) -> Result<()> {
+        for file in self.files.iter() {
+            ensure!(
+                file.components().all(|c| matches!(c, Component::Normal(_))),
+                "Package files must be relative and stay inside the prefix: {}",
+                file.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl FreightArchive {
+    /// Bundles the files listed in the manifest, taken from `root`, into
+    /// `<out_dir>/<name>-<version>.freight`.
+    pub fn pack(manifest: Manifest, root: &Path, out_dir: &Path) -> Result<Self> {
+        manifest.check_files()?;
+        let work = WorkDir::new("pack")?;
+
+        for file in manifest.files.iter() {
+            let dest = work.0.join(FILES_DIR).join(file);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(root.join(file), dest)?;
+        }
+        fs::create_dir_all(work.0.join(FILES_DIR))?;
+
+        let f = fs::File::create(work.0.join(MANIFEST_NAME))?;
+        json::to_writer_pretty(f, &manifest)?;
+
+        fs::create_dir_all(out_dir)?;
+        let path = out_dir.join(format!(
+            "{}-{}.{EXTENSION}",
+            manifest.name, manifest.version
+        ));
+        cmd::status(
+            Command::new("tar")
+                .arg("-czf")
+                .arg(&path)
+                .arg("-C")
+                .arg(&work.0)
+                .arg(MANIFEST_NAME)
+                .arg(FILES_DIR),
+        )
+        .wrap_err_with(|| format!("Failed to create {}", path.display()))?;
+
+        Ok(Self { path, manifest })
+    }
+
+    /// Reads the manifest of an archive without unpacking the rest of it.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let out = cmd::raw_output(
+            Command::new("tar")
+                .arg("-xzOf")
+                .arg(&path)
+                .arg(MANIFEST_NAME),
+        )?;
+        ensure!(
+            out.status.success(),
+            "Not a freight package {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+        let manifest: Manifest = json::from_slice(&out.stdout)?;
+        manifest
+            .check_files()
+            .wrap_err_with(|| format!("Refusing to open {}", path.display()))?;
+        Ok(Self { path, manifest })
+    }
+
+    /// Copies the files of the package into the prefix, returning where they
+    /// ended up. Only files named in the manifest are installed.
+    pub fn unpack(&self, prefix: &Path) -> Result<Vec<PathBuf>> {
+        self.manifest.check_files()?;
+        let work = WorkDir::new("unpack")?;
+        cmd::status(
+            Command::new("tar")
+                .arg("-xzf")
+                .arg(&self.path)
+                .arg("-C")
+                .arg(&work.0),
+        )
+        .wrap_err_with(|| format!("Failed to unpack {}", self.path.display()))?;
+
+        // everything is copied next to where it goes first and only moved
+        // into place once all of it made it, so a failure halfway leaves the
+        // prefix as it was
+        let mut staged = Vec::new();
+        let res = self.manifest.files.iter().try_for_each(|file| {
+            let src = work.0.join(FILES_DIR).join(file);
+            let dest = prefix.join(file);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let file_name = dest.file_name().unwrap_or_default().to_string_lossy();
+            let tmp = dest.with_file_name(format!(".{file_name}.freight-new"));
+            staged.push((tmp.clone(), dest));
+            // copy keeps the permissions of the packed file
+            fs::copy(&src, &tmp)?;
+            Ok(())
+        });
+        if let Err(e) = res {
+            for (tmp, _) in staged.iter().filter(|(tmp, _)| tmp.exists()) {
+                fs::remove_file(tmp)?;
+            }
+            return Err(e);
+        }
+
+        let mut installed = Vec::new();
+        for (tmp, dest) in staged {
+            fs::rename(tmp, &dest)?;
+            installed.push(dest);
+        }
+        Ok(installed)
+    }
+}
+
+/// Orders versions by comparing the numeric parts as numbers and everything
+/// else as text, so `1.10` is newer than `1.9`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| -> Vec<String> {
+        v.split(['.', '-', '_', '+'])
+            .map(ToString::to_string)
+            .collect()
+    };
+    let (a, b) = (split(a), split(b));
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
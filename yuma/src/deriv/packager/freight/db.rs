@@ -0,0 +1,75 @@
+use crate::prelude::*;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// What we know about a single installed freight package.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstalledPackage {
+    pub version: String,
+    /// Absolute paths of the files this package owns.
+    pub files: Vec<PathBuf>,
+    pub dependencies: Vec<String>,
+    /// Whether this was asked for or only pulled in as a dependency.
+    pub explicit: bool,
+}
+
+/// The record of everything freight installed into a prefix, stored at
+/// `<prefix>/var/lib/freight/db.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstallDb {
+    #[serde(skip)]
+    path: PathBuf,
+    pub packages: BTreeMap<String, InstalledPackage>,
+}
+
+impl InstallDb {
+    pub fn path_in(prefix: &Path) -> PathBuf {
+        prefix
+            .join("var")
+            .join("lib")
+            .join("freight")
+            .join("db.json")
+    }
+
+    /// Loads the database of a prefix, starting out empty if there is none.
+    pub fn load(prefix: &Path) -> Result<Self> {
+        let path = Self::path_in(prefix);
+        if !path.exists() {
+            return Ok(Self { path, ..default() });
+        }
+
+        let f = fs::File::open(&path)?;
+        let mut db: InstallDb = json::from_reader(f)?;
+        db.path = path;
+        Ok(db)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let f = fs::File::create(&self.path)?;
+        json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+
+    /// Finds the package that owns a file, if any.
+    pub fn owner(&self, file: &Path) -> Option<&str> {
+        self.packages
+            .iter()
+            .find(|(_, pkg)| pkg.files.iter().any(|f| f == file))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Names of the installed packages that depend on `name`.
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        self.packages
+            .iter()
+            .filter(|(_, pkg)| pkg.dependencies.iter().any(|dep| dep == name))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
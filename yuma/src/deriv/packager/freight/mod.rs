@@ -0,0 +1,266 @@
+mod archive;
+mod db;
+
+pub use self::archive::{compare_versions, FreightArchive, Manifest, EXTENSION};
+pub use self::db::{InstallDb, InstalledPackage};
+
+use crate::prelude::*;
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, ensure};
+
+use super::PackageBackend;
+
+/// Installs native freight packages from a directory of archives into a
+/// prefix. Everything installed is recorded in an [`InstallDb`] so files can
+/// be upgraded and removed cleanly.
+#[derive(Debug)]
+pub struct FreightPackager {
+    prefix: PathBuf,
+    repo: PathBuf,
+}
+
+impl Default for FreightPackager {
+    fn default() -> Self {
+        Self::new(Self::default_prefix(), Self::default_repo())
+    }
+}
+
+impl FreightPackager {
+    pub fn new(prefix: impl Into<PathBuf>, repo: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            repo: repo.into(),
+        }
+    }
+
+    /// `~/.local`
+    pub fn default_prefix() -> PathBuf {
+        let home = env::var_os("HOME").unwrap_or_default();
+        Path::new(&home).join(".local")
+    }
+
+    /// `~/.local/share/freight/repo`
+    pub fn default_repo() -> PathBuf {
+        Self::default_prefix()
+            .join("share")
+            .join("freight")
+            .join("repo")
+    }
+
+    /// The newest archive in the repo for every package name.
+    fn available(&self) -> Result<HashMap<String, FreightArchive>> {
+        let mut newest: HashMap<String, FreightArchive> = HashMap::new();
+        if !self.repo.exists() {
+            return Ok(newest);
+        }
+
+        for entry in fs::read_dir(&self.repo)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+
+            let archive = FreightArchive::open(path)?;
+            let is_newer = newest.get(&archive.manifest.name).is_none_or(|old| {
+                compare_versions(&archive.manifest.version, &old.manifest.version)
+                    == Ordering::Greater
+            });
+            if is_newer {
+                newest.insert(archive.manifest.name.clone(), archive);
+            }
+        }
+        Ok(newest)
+    }
+
+    /// Puts `name` and everything it needs into `order`, dependencies first.
+    fn resolve<'a>(
+        available: &'a HashMap<String, FreightArchive>,
+        name: &str,
+        order: &mut Vec<&'a FreightArchive>,
+        stack: &mut Vec<String>,
+    ) -> Result<()> {
+        if order.iter().any(|a| a.manifest.name == name) {
+            return Ok(());
+        }
+        ensure!(
+            !stack.iter().any(|n| n == name),
+            "Dependency cycle: {} -> {name}",
+            stack.join(" -> ")
+        );
+
+        let archive = available
+            .get(name)
+            .ok_or_else(|| YumaError::InvalidPackage {
+                name: name.to_string(),
+            })?;
+
+        stack.push(name.to_string());
+        for dep in archive.manifest.dependencies.iter() {
+            Self::resolve(available, dep, order, stack)?;
+        }
+        stack.pop();
+
+        order.push(archive);
+        Ok(())
+    }
+
+    fn install_archive(
+        &self,
+        db: &mut InstallDb,
+        archive: &FreightArchive,
+        explicit: bool,
+    ) -> Result<()> {
+        let manifest = &archive.manifest;
+        let old = db.packages.get(&manifest.name).cloned();
+
+        // never overwrite something that belongs to another package or that
+        // was put there by hand
+        for file in manifest.files.iter() {
+            let dest = self.prefix.join(file);
+            match db.owner(&dest) {
+                Some(owner) => ensure!(
+                    owner == manifest.name,
+                    "{} from {} conflicts with {owner}",
+                    dest.display(),
+                    manifest.name
+                ),
+                None => ensure!(
+                    !dest.exists(),
+                    "{} from {} already exists and belongs to no package",
+                    dest.display(),
+                    manifest.name
+                ),
+            }
+        }
+
+        match &old {
+            Some(old) => info!(
+                "Upgrading {} {} -> {}",
+                manifest.name, old.version, manifest.version
+            ),
+            None => info!("Installing {} {}", manifest.name, manifest.version),
+        }
+
+        let files = archive.unpack(&self.prefix)?;
+
+        if let Some(old) = &old {
+            for file in old.files.iter().filter(|f| !files.contains(f)) {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+
+        db.packages.insert(
+            manifest.name.clone(),
+            InstalledPackage {
+                version: manifest.version.clone(),
+                files,
+                dependencies: manifest.dependencies.clone(),
+                // once something was asked for it stays that way
+                explicit: explicit || old.is_some_and(|old| old.explicit),
+            },
+        );
+        db.save()
+    }
+}
+
+impl PackageBackend for FreightPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let db = InstallDb::load(&self.prefix)?;
+        let available = self.available()?;
+
+        // an outdated package counts as missing so the next update upgrades it
+        Ok(db
+            .packages
+            .into_iter()
+            .filter(|(name, pkg)| {
+                available
+                    .get(name)
+                    .is_none_or(|a| a.manifest.version == pkg.version)
+            })
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let db = InstallDb::load(&self.prefix)?;
+        Ok(db
+            .packages
+            .iter()
+            .filter(|(name, pkg)| pkg.explicit && db.dependents(name).is_empty())
+            .map(|(name, _)| name.clone())
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let available = self.available()?;
+        let mut db = InstallDb::load(&self.prefix)?;
+
+        let mut order = Vec::new();
+        for name in pkgs.iter() {
+            Self::resolve(&available, name, &mut order, &mut vec![])?;
+        }
+
+        for archive in order {
+            let name = &archive.manifest.name;
+            let explicit = pkgs.contains(name);
+            let current = db
+                .packages
+                .get(name)
+                .is_some_and(|pkg| pkg.version == archive.manifest.version);
+
+            if current {
+                if explicit {
+                    db.packages.get_mut(name).unwrap().explicit = true;
+                    db.save()?;
+                }
+                continue;
+            }
+
+            self.install_archive(&mut db, archive, explicit)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let mut db = InstallDb::load(&self.prefix)?;
+
+        for name in pkgs.iter() {
+            let needed_by: Vec<&str> = db
+                .dependents(name)
+                .into_iter()
+                .filter(|dep| !pkgs.iter().any(|p| p == dep))
+                .collect();
+            if !needed_by.is_empty() {
+                bail!("Can not remove {name}, it is needed by {needed_by:?}");
+            }
+        }
+
+        for name in pkgs {
+            let Some(pkg) = db.packages.remove(&name) else {
+                continue;
+            };
+            info!("Removing {} {}", name, pkg.version);
+            for file in pkg.files {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+
+        db.save()
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,147 @@
+use crate::cmd;
+use crate::prelude::*;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{InstallReason, PackageBackend, PackageInfo};
+
+/// Tools installed with `go install`. Packages are named by their import path
+/// (`golang.org/x/tools/gopls`) and get installed at `@latest` unless a
+/// version is given, either as a pin or like `golang.org/x/tools/gopls@v0.15.0`.
+#[derive(Debug, Default)]
+pub struct GoPackager;
+
+impl GoPackager {
+    /// The directory `go install` puts binaries in.
+    fn bin_dir(&self) -> Result<PathBuf> {
+        let stdout = super::output(Command::new("go").arg("env").arg("GOBIN").arg("GOPATH"))?;
+        let mut lines = stdout.lines();
+        let gobin = lines.next().unwrap_or_default();
+        let gopath = lines.next().unwrap_or_default();
+
+        if gobin.is_empty() {
+            // GOPATH can be a list, go install always uses the first one
+            let gopath = gopath.split(':').next().unwrap_or_default();
+            Ok(Path::new(gopath).join("bin"))
+        } else {
+            Ok(PathBuf::from(gobin))
+        }
+    }
+
+    /// Every go binary in the bin dir along with the package path and
+    /// module version it was built from. This information is embedded in
+    /// the binary by the go toolchain.
+    fn binaries(&self) -> Result<Vec<GoBinary>> {
+        let Ok(dir) = fs::read_dir(self.bin_dir()?) else {
+            return Ok(vec![]);
+        };
+
+        // anything that is not a go binary fails `go version` and is skipped
+        Ok(dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter_map(|bin| {
+                let stdout = cmd::raw_output(Command::new("go").arg("version").arg("-m").arg(&bin))
+                    .ok()?
+                    .stdout;
+                let stdout = String::from_utf8(stdout).ok()?;
+                // looks like `\tpath\tgolang.org/x/tools/gopls` followed by
+                // `\tmod\tgolang.org/x/tools/gopls\tv0.15.0\th1:...`
+                let field = |key: &str| {
+                    stdout.lines().find_map(|line| {
+                        let mut fields = line.split_whitespace();
+                        (fields.next()? == key).then(|| fields.collect::<Vec<_>>())
+                    })
+                };
+                let path = field("path")?.first()?.to_string();
+                let version = field("mod")
+                    .and_then(|fields| fields.get(1).map(ToString::to_string))
+                    .unwrap_or_default();
+                Some(GoBinary { path, version, bin })
+            })
+            .collect())
+    }
+}
+
+struct GoBinary {
+    path: String,
+    version: String,
+    bin: PathBuf,
+}
+
+impl PackageBackend for GoPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(self.binaries()?.into_iter().map(|b| b.path).collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.list_installed()
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        // packages from different modules cant be installed in one call
+        for pkg in pkgs {
+            super::status(
+                Command::new("go")
+                    .arg("install")
+                    .arg(format!("{pkg}@latest")),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        // go has no uninstall so just delete the binaries it built
+        for binary in self.binaries()? {
+            if pkgs.contains(&binary.path) {
+                fs::remove_file(binary.bin)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        Ok(self
+            .binaries()?
+            .into_iter()
+            .map(|b| PackageInfo {
+                name: b.path,
+                version: b.version,
+                description: None,
+                repository: None,
+                installed_size: None,
+                reason: Some(InstallReason::Explicit),
+            })
+            .collect())
+    }
+
+    /// Import paths never contain an `@` so anything after one is a version.
+    /// `@latest` is what gets installed anyway.
+    fn split_version(&self, name: super::SpecficName) -> (super::SpecficName, Option<String>) {
+        match name.split_once('@') {
+            Some((path, "latest")) => (path.to_string(), None),
+            Some((path, version)) => (path.to_string(), Some(version.to_string())),
+            None => (name, None),
+        }
+    }
+
+    fn install_version(
+        &self,
+        pkg: super::SpecficName,
+        version: &str,
+    ) -> Result<super::SpecficName> {
+        super::status(
+            Command::new("go")
+                .arg("install")
+                .arg(format!("{pkg}@{version}")),
+        )?;
+        Ok(pkg)
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,42 @@
+use crate::prelude::*;
+
+/// What a backend knows about a single package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// Where the package comes from, like `core` or `aur` for paru and
+    /// `homebrew/core` for brew.
+    pub repository: Option<String>,
+    /// In bytes.
+    pub installed_size: Option<u64>,
+    /// Why the package is installed, [`None`] when it is not.
+    pub reason: Option<InstallReason>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstallReason {
+    /// Asked for by name.
+    Explicit,
+    /// Pulled in by another package.
+    Dependency,
+}
+
+/// A package with a newer version available.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Upgrade {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl PackageInfo {
+    pub fn is_installed(&self) -> bool {
+        self.reason.is_some()
+    }
+
+    pub fn is_explicit(&self) -> bool {
+        self.reason == Some(InstallReason::Explicit)
+    }
+}
@@ -1,28 +1,91 @@
+mod apk;
+mod apt;
 mod brew;
 mod cargo;
+mod custom;
+pub mod detect;
+mod dnf;
 mod fake;
+mod flatpak;
+pub mod freight;
+mod go;
+mod info;
+mod nix;
+mod npm;
 mod paru;
+mod pin;
+mod pipx;
+pub mod plugin;
+mod protect;
+mod recipe;
+mod release;
+mod repo;
+mod rustup;
+mod search;
+mod xbps;
 
 use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
 use std::sync::Arc;
 
 use crate::prelude::*;
 
+pub use self::apk::ApkPackager;
+pub use self::apt::AptPackager;
 pub use self::brew::BrewPackager;
 pub use self::cargo::CargoPackager;
+pub use self::custom::CustomPackager;
+pub use self::dnf::DnfPackager;
 pub use self::fake::FakePackager;
+pub use self::flatpak::{FlatpakPackager, FlatpakScope};
+pub use self::freight::FreightPackager;
+pub use self::go::GoPackager;
+pub use self::info::{InstallReason, PackageInfo, Upgrade};
+pub use self::nix::NixPackager;
+pub use self::npm::NpmPackager;
 pub use self::paru::ParuPackager;
+pub use self::pin::{version_matches, Pin};
+pub use self::pipx::PipxPackager;
+pub use self::plugin::PluginPackager;
+pub use self::protect::is_protected;
+pub use self::recipe::{Recipe, RecipePackager, Source};
+pub use self::release::{Release, ReleasePackager};
+pub use self::repo::Repo;
+pub use self::rustup::RustupPackager;
+pub use self::search::{search, search_with, SearchHit};
+pub use self::xbps::XbpsPackager;
+
+pub(crate) use crate::cmd::{output, status};
 
 type ParuRc = Arc<self::ParuPackager>;
 type BrewRc = Arc<self::BrewPackager>;
 type CargRc = Arc<self::CargoPackager>;
 type FakeRc = Arc<self::FakePackager>;
+type AptRc = Arc<self::AptPackager>;
+type DnfRc = Arc<self::DnfPackager>;
+type ApkRc = Arc<self::ApkPackager>;
+type XbpsRc = Arc<self::XbpsPackager>;
+type PipxRc = Arc<self::PipxPackager>;
+type NpmRc = Arc<self::NpmPackager>;
+type GoRc = Arc<self::GoPackager>;
+type NixRc = Arc<self::NixPackager>;
+type RustupRc = Arc<self::RustupPackager>;
 
 thread_local! {
 pub static PARU_PACKAGER: ParuRc = default();
 pub static BREW_PACKAGER: BrewRc = default();
 pub static CARG_PACKAGER: CargRc = default();
 pub static FAKE_PACKAGER: FakeRc = default();
+pub static APT_PACKAGER: AptRc = default();
+pub static DNF_PACKAGER: DnfRc = default();
+pub static APK_PACKAGER: ApkRc = default();
+pub static XBPS_PACKAGER: XbpsRc = default();
+pub static PIPX_PACKAGER: PipxRc = default();
+pub static NPM_PACKAGER: NpmRc = default();
+pub static GO_PACKAGER: GoRc = default();
+pub static NIX_PACKAGER: NixRc = default();
+pub static RUSTUP_PACKAGER: RustupRc = default();
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -33,27 +96,207 @@ impl GenericName {
     }
 }
 
+impl From<GenericName> for SpecficName {
+    fn from(name: GenericName) -> Self {
+        name.0
+    }
+}
+
 pub type SpecficName = String;
 
 pub trait PackageBackend {
-    fn list_installed(&self) -> Vec<String>;
+    fn list_installed(&self) -> Result<Vec<String>>;
 
-    fn list_leaves(&self) -> Vec<String>;
+    fn list_leaves(&self) -> Result<Vec<String>>;
 
     fn install(&self, pkgs: Vec<SpecficName>) -> Result<()>;
 
     fn remove(&self, pkgs: Vec<SpecficName>) -> Result<()>;
 
     fn resolve_name(&self, name: GenericName) -> SpecficName;
+
+    /// Details on everything that is installed.
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        Err(YumaError::Unsupported {
+            op: "give package info",
+        }
+        .into())
+    }
+
+    /// Details on a single package, installed or not. [`None`] when the
+    /// backend doesn't know the package.
+    fn info(&self, _name: &str) -> Result<Option<PackageInfo>> {
+        Err(YumaError::Unsupported {
+            op: "give package info",
+        }
+        .into())
+    }
+
+    /// Packages available from this backend matching `query`. Results don't
+    /// say whether a package is installed so their `reason` is always
+    /// [`None`].
+    fn search(&self, _query: &str) -> Result<Vec<PackageInfo>> {
+        Err(YumaError::Unsupported { op: "search" }.into())
+    }
+
+    /// Splits off a version that is part of a declared name, like the
+    /// `@v0.15.0` of `golang.org/x/tools/gopls@v0.15.0` for go. It is then
+    /// treated like a version given with [`PkgBuilder::version`].
+    ///
+    /// [`PkgBuilder::version`]: crate::deriv::pkg::builder::PkgBuilder::version
+    fn split_version(&self, name: SpecficName) -> (SpecficName, Option<String>) {
+        (name, None)
+    }
+
+    /// The name `pkg` pinned to `version` is installed as. Only differs for
+    /// backends that put versions in the name, which fail for versions they
+    /// have no package for.
+    fn pinned_name(&self, pkg: &str, _version: &str) -> Result<SpecficName> {
+        Ok(pkg.to_string())
+    }
+
+    /// Installs a version of `pkg` matching `version` (see [`version_matches`])
+    /// and returns the name it ended up installed as, which differs for
+    /// backends that put versions in the name.
+    fn install_version(&self, _pkg: SpecficName, _version: &str) -> Result<SpecficName> {
+        Err(YumaError::Unsupported {
+            op: "install specific versions",
+        }
+        .into())
+    }
+
+    /// Keeps packages at their current version when upgrading.
+    fn hold(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Err(YumaError::Unsupported {
+            op: "hold packages",
+        }
+        .into())
+    }
+
+    fn unhold(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Err(YumaError::Unsupported {
+            op: "hold packages",
+        }
+        .into())
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        Err(YumaError::Unsupported {
+            op: "hold packages",
+        }
+        .into())
+    }
+
+    /// Packages that must never be pruned, like the kernel or the package
+    /// manager itself. Entries ending in `*` match by prefix, see
+    /// [`is_protected`].
+    fn protected(&self) -> Vec<SpecficName> {
+        vec![]
+    }
+
+    /// The repositories this packager added and may remove again. The ones
+    /// that came with the system are left out.
+    fn list_repos(&self) -> Result<Vec<String>> {
+        Err(YumaError::Unsupported {
+            op: "manage repositories",
+        }
+        .into())
+    }
+
+    /// Adds a repository, or updates it when it changed since it was added.
+    fn add_repo(&self, _repo: &Repo) -> Result<()> {
+        Err(YumaError::Unsupported {
+            op: "manage repositories",
+        }
+        .into())
+    }
+
+    /// Removes repositories returned by [`PackageBackend::list_repos`].
+    fn remove_repos(&self, _names: Vec<String>) -> Result<()> {
+        Err(YumaError::Unsupported {
+            op: "manage repositories",
+        }
+        .into())
+    }
+
+    /// Dependencies that nothing installed needs anymore.
+    fn list_orphans(&self) -> Result<Vec<SpecficName>> {
+        Err(YumaError::Unsupported { op: "list orphans" }.into())
+    }
+
+    /// Removes orphans found by [`PackageBackend::list_orphans`].
+    fn remove_orphans(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.remove(pkgs)
+    }
+
+    /// Everything removing `pkgs` would take with it, including `pkgs`
+    /// themselves. Backends whose removals don't cascade don't need to
+    /// override this.
+    fn removal_plan(&self, pkgs: &[SpecficName]) -> Result<Vec<SpecficName>> {
+        Ok(pkgs.to_vec())
+    }
+
+    /// Installed packages that have a newer version available.
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        Err(YumaError::Unsupported {
+            op: "upgrade packages",
+        }
+        .into())
+    }
+
+    /// Upgrades exactly these packages to their newest version.
+    fn upgrade(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Err(YumaError::Unsupported {
+            op: "upgrade packages",
+        }
+        .into())
+    }
+}
+
+/// Creates a command for a program that needs root, going through `sudo` when
+/// we are not already running as root. When running unattended `sudo` fails
+/// instead of asking for a password.
+pub(crate) fn elevated(program: &str) -> Command {
+    if ::nix::unistd::geteuid().is_root() {
+        Command::new(program)
+    } else {
+        let mut cmd = Command::new("sudo");
+        if crate::prompt::is_unattended() {
+            cmd.arg("--non-interactive");
+        }
+        cmd.arg(program);
+        cmd
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "PackagerRepr", into = "PackagerRepr")]
 pub struct Packager {
     _packager_type: PackagerType,
-    #[serde(skip)]
     backend: SyncPackagerBackend,
 }
 
+/// What a [`Packager`] looks like serialized. The backend is rebuilt from the
+/// type when it is read back.
+#[derive(Serialize, Deserialize)]
+struct PackagerRepr {
+    _packager_type: PackagerType,
+}
+
+impl From<PackagerRepr> for Packager {
+    fn from(value: PackagerRepr) -> Self {
+        value._packager_type.into()
+    }
+}
+
+impl From<Packager> for PackagerRepr {
+    fn from(value: Packager) -> Self {
+        Self {
+            _packager_type: value._packager_type,
+        }
+    }
+}
+
 impl PartialEq for Packager {
     fn eq(&self, other: &Self) -> bool {
         self._packager_type == other._packager_type
@@ -63,12 +306,6 @@ impl Eq for Packager {}
 
 #[derive(Clone)]
 struct SyncPackagerBackend(Arc<dyn PackageBackend>);
-impl Default for SyncPackagerBackend {
-    fn default() -> Self {
-        let Packager { backend, .. } = Packager::guess();
-        backend
-    }
-}
 
 impl fmt::Debug for Packager {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -80,14 +317,33 @@ impl fmt::Debug for Packager {
 
 impl Packager {
     /// Based on the operating system and installed packages make the best guess
-    /// for the package backend to use
+    /// for the package backend to use. See [`detect`] for how it is picked.
+    ///
+    /// When nothing matches this still returns a packager so that declaring
+    /// packages can't fail but anything it is asked to do returns an error.
     pub fn guess() -> Self {
-        // HACK: this gets it working for just my system but this should be a smarter system
-        #[cfg(target_arch = "x86_64")]
-        return Self::paru();
+        match detect::detected() {
+            Ok(ptype) => ptype.into(),
+            Err(reason) => Self::undetected(reason),
+        }
+    }
+
+    /// Like [`Packager::guess`] but fails when no packager could be detected.
+    pub fn detect() -> Result<Self> {
+        detect::detected()
+            .map(Into::into)
+            .map_err(|reason| resu::eyre::eyre!(reason))
+    }
+
+    pub fn packager_type(&self) -> &PackagerType {
+        &self._packager_type
+    }
 
-        #[cfg(not(target_arch = "x86_64"))]
-        return Self::brew();
+    fn undetected(reason: String) -> Self {
+        Self {
+            _packager_type: PackagerType::Undetected,
+            backend: SyncPackagerBackend(Arc::new(detect::UndetectedPackager::new(reason))),
+        }
     }
 
     pub fn paru() -> Self {
@@ -104,6 +360,156 @@ impl Packager {
         }
     }
 
+    pub fn apt() -> Self {
+        Self {
+            _packager_type: PackagerType::Apt,
+            backend: SyncPackagerBackend(APT_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn dnf() -> Self {
+        Self {
+            _packager_type: PackagerType::Dnf,
+            backend: SyncPackagerBackend(DNF_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn apk() -> Self {
+        Self {
+            _packager_type: PackagerType::Apk,
+            backend: SyncPackagerBackend(APK_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn xbps() -> Self {
+        Self {
+            _packager_type: PackagerType::Xbps,
+            backend: SyncPackagerBackend(XBPS_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn cargo() -> Self {
+        Self {
+            _packager_type: PackagerType::Cargo,
+            backend: SyncPackagerBackend(CARG_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn pipx() -> Self {
+        Self {
+            _packager_type: PackagerType::Pipx,
+            backend: SyncPackagerBackend(PIPX_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn npm() -> Self {
+        Self {
+            _packager_type: PackagerType::Npm,
+            backend: SyncPackagerBackend(NPM_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn go() -> Self {
+        Self {
+            _packager_type: PackagerType::Go,
+            backend: SyncPackagerBackend(GO_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn nix() -> Self {
+        Self {
+            _packager_type: PackagerType::Nix,
+            backend: SyncPackagerBackend(NIX_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    pub fn rustup() -> Self {
+        Self {
+            _packager_type: PackagerType::Rustup,
+            backend: SyncPackagerBackend(RUSTUP_PACKAGER.with(Clone::clone)),
+        }
+    }
+
+    /// Flatpak apps are split up by installation and remote so each
+    /// combination gets its own backend instead of a shared one.
+    pub fn flatpak(scope: FlatpakScope, remote: impl Into<String>) -> Self {
+        let remote = remote.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(FlatpakPackager::new(scope, remote.clone()))),
+            _packager_type: PackagerType::Flatpak { scope, remote },
+        }
+    }
+
+    /// Releases are grouped by the directory their binaries go into.
+    pub fn release(bin_dir: impl Into<PathBuf>) -> Self {
+        let bin_dir = bin_dir.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(ReleasePackager::new(bin_dir.clone()))),
+            _packager_type: PackagerType::Release { bin_dir },
+        }
+    }
+
+    /// Recipes are grouped by the prefix they install into.
+    pub fn recipe(prefix: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(RecipePackager::new(prefix.clone()))),
+            _packager_type: PackagerType::Recipe { prefix },
+        }
+    }
+
+    /// Native freight packages are grouped by where they are installed to and
+    /// which repo they come from.
+    pub fn freight(prefix: impl Into<PathBuf>, repo: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into();
+        let repo = repo.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(FreightPackager::new(
+                prefix.clone(),
+                repo.clone(),
+            ))),
+            _packager_type: PackagerType::Freight { prefix, repo },
+        }
+    }
+
+    /// Registers a backend under `name` so it can be used like any builtin
+    /// packager, including by name and from a cache.
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use yuma::deriv::packager::{FakePackager, PackagerType};
+    /// # use yuma::prelude::*;
+    /// let inhouse = Packager::register("inhouse", Arc::new(FakePackager)).unwrap();
+    ///
+    /// assert_eq!(PackagerType::try_from("inhouse").unwrap(), PackagerType::Custom("inhouse".into()));
+    /// assert_eq!(inhouse.list_installed().unwrap(), Vec::<String>::new());
+    /// ```
+    pub fn register(name: impl Into<String>, backend: Arc<dyn PackageBackend>) -> Result<Self> {
+        let name = name.into();
+        custom::register(name.clone(), backend)?;
+        Ok(Self::custom(name))
+    }
+
+    /// A packager registered with [`Packager::register`]. The backend does not
+    /// need to be registered yet but using it before it is fails.
+    pub fn custom(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(CustomPackager::new(name.clone()))),
+            _packager_type: PackagerType::Custom(name),
+        }
+    }
+
+    /// A backend implemented by the `freight-backend-<name>` executable, see
+    /// [`plugin`] for how they talk to each other.
+    pub fn plugin(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            backend: SyncPackagerBackend(Arc::new(PluginPackager::new(name.clone()))),
+            _packager_type: PackagerType::Plugin(name),
+        }
+    }
+
     pub fn fake() -> Self {
         Self {
             _packager_type: PackagerType::Fake,
@@ -132,27 +538,94 @@ impl std::ops::Deref for Packager {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PackagerType {
     Paru,
     Brew,
     Fake,
-    // PkgBuild,
-    // Justfile,
+    Apt,
+    Dnf,
+    Apk,
+    Xbps,
+    Cargo,
+    Pipx,
+    Npm,
+    Go,
+    Nix,
+    Rustup,
+    Flatpak {
+        scope: FlatpakScope,
+        remote: String,
+    },
+    Release {
+        bin_dir: PathBuf,
+    },
+    Recipe {
+        prefix: PathBuf,
+    },
+    Freight {
+        prefix: PathBuf,
+        repo: PathBuf,
+    },
+    /// No packager could be detected for this system.
+    Undetected,
+    /// A backend registered at runtime with [`Packager::register`].
+    Custom(String),
+    /// An executable on `PATH`, see [`plugin`].
+    Plugin(String),
     // CargoToml,
     // Portage,
-    // Frieght,
+}
+
+impl PackagerType {
+    /// Looks up one of the packagers that come with yuma by name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "Paru" | "paru" | "Paur" | "paur" => Some(PackagerType::Paru),
+            "Brew" | "brew" => Some(PackagerType::Brew),
+            "Apt" | "apt" => Some(PackagerType::Apt),
+            "Dnf" | "dnf" => Some(PackagerType::Dnf),
+            "Apk" | "apk" => Some(PackagerType::Apk),
+            "Xbps" | "xbps" => Some(PackagerType::Xbps),
+            "Cargo" | "cargo" => Some(PackagerType::Cargo),
+            "Pipx" | "pipx" => Some(PackagerType::Pipx),
+            "Npm" | "npm" => Some(PackagerType::Npm),
+            "Go" | "go" => Some(PackagerType::Go),
+            "Nix" | "nix" => Some(PackagerType::Nix),
+            "Rustup" | "rustup" => Some(PackagerType::Rustup),
+            "Flatpak" | "flatpak" => Some(PackagerType::Flatpak {
+                scope: FlatpakScope::default(),
+                remote: flatpak::DEFAULT_REMOTE.into(),
+            }),
+            "Release" | "release" => Some(PackagerType::Release {
+                bin_dir: ReleasePackager::default_bin_dir(),
+            }),
+            "Recipe" | "recipe" => Some(PackagerType::Recipe {
+                prefix: RecipePackager::default_prefix(),
+            }),
+            "Freight" | "freight" => Some(PackagerType::Freight {
+                prefix: FreightPackager::default_prefix(),
+                repo: FreightPackager::default_repo(),
+            }),
+            _ => None,
+        }
+    }
 }
 
 impl TryFrom<&str> for PackagerType {
     type Error = resu::eyre::Report;
 
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        match value {
-            "Paur" | "paur" => Ok(PackagerType::Paru),
-            "Brew" | "brew" => Ok(PackagerType::Brew),
-            name => Err(resu::eyre::eyre!("Unkown packager: {}", name)),
+        if let Some(ptype) = PackagerType::builtin(value) {
+            return Ok(ptype);
+        }
+        if custom::is_registered(value) {
+            return Ok(PackagerType::Custom(value.to_owned()));
+        }
+        if PluginPackager::exists(value) {
+            return Ok(PackagerType::Plugin(value.to_owned()));
         }
+        Err(resu::eyre::eyre!("Unkown packager: {}", value))
     }
 }
 
@@ -162,6 +635,23 @@ impl From<PackagerType> for Packager {
             PackagerType::Paru => Packager::paru(),
             PackagerType::Brew => Packager::brew(),
             PackagerType::Fake => Packager::fake(),
+            PackagerType::Apt => Packager::apt(),
+            PackagerType::Dnf => Packager::dnf(),
+            PackagerType::Apk => Packager::apk(),
+            PackagerType::Xbps => Packager::xbps(),
+            PackagerType::Cargo => Packager::cargo(),
+            PackagerType::Pipx => Packager::pipx(),
+            PackagerType::Npm => Packager::npm(),
+            PackagerType::Go => Packager::go(),
+            PackagerType::Nix => Packager::nix(),
+            PackagerType::Rustup => Packager::rustup(),
+            PackagerType::Flatpak { scope, remote } => Packager::flatpak(scope, remote),
+            PackagerType::Release { bin_dir } => Packager::release(bin_dir),
+            PackagerType::Recipe { prefix } => Packager::recipe(prefix),
+            PackagerType::Freight { prefix, repo } => Packager::freight(prefix, repo),
+            PackagerType::Undetected => Packager::guess(),
+            PackagerType::Custom(name) => Packager::custom(name),
+            PackagerType::Plugin(name) => Packager::plugin(name),
         }
     }
 }
@@ -0,0 +1,110 @@
+use crate::prelude::*;
+
+use std::process::Command;
+
+use super::PackageBackend;
+
+/// Manages the entries of the default nix profile. Packages are named by their
+/// flake reference like `nixpkgs#ripgrep`.
+#[derive(Debug, Default)]
+pub struct NixPackager;
+
+#[derive(Debug, Deserialize)]
+struct ProfileElement {
+    #[serde(rename = "attrPath")]
+    attr_path: Option<String>,
+    #[serde(rename = "originalUrl")]
+    original_url: Option<String>,
+}
+
+impl ProfileElement {
+    /// Turns the element back into the flake reference it was installed with.
+    /// `flake:nixpkgs` and `legacyPackages.x86_64-linux.ripgrep` become
+    /// `nixpkgs#ripgrep`.
+    fn flake_ref(&self) -> Option<String> {
+        let url = self.original_url.as_deref()?;
+        let url = url.strip_prefix("flake:").unwrap_or(url);
+        let attr = self.attr_path.as_deref()?;
+        let attr = match attr.split_once('.') {
+            Some(("legacyPackages" | "packages", rest)) => {
+                rest.split_once('.').map_or(rest, |(_system, attr)| attr)
+            }
+            _ => attr,
+        };
+        Some(format!("{url}#{attr}"))
+    }
+}
+
+impl NixPackager {
+    /// Pairs every flake reference in the profile with the handle that
+    /// `nix profile remove` accepts for it.
+    fn elements(&self) -> Result<Vec<(String, String)>> {
+        let stdout = super::output(Command::new("nix").arg("profile").arg("list").arg("--json"))?;
+        let mut list: json::Value = json::from_str(&stdout)?;
+
+        let elements = match list["elements"].take() {
+            // newer versions of nix name each element
+            json::Value::Object(elements) => elements
+                .into_iter()
+                .filter_map(|(name, elem)| {
+                    let elem: ProfileElement = json::from_value(elem).ok()?;
+                    Some((elem.flake_ref()?, name))
+                })
+                .collect(),
+            // older ones only know elements by their index
+            json::Value::Array(elements) => elements
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, elem)| {
+                    let elem: ProfileElement = json::from_value(elem).ok()?;
+                    Some((elem.flake_ref()?, index.to_string()))
+                })
+                .collect(),
+            _ => vec![],
+        };
+        Ok(elements)
+    }
+}
+
+impl PackageBackend for NixPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(self
+            .elements()?
+            .into_iter()
+            .map(|(flake, _)| flake)
+            .collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        // a profile only ever contains what was explicitly installed
+        self.list_installed()
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(Command::new("nix").arg("profile").arg("install").args(pkgs))
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let handles: Vec<String> = self
+            .elements()?
+            .into_iter()
+            .filter(|(flake, _)| pkgs.contains(flake))
+            .map(|(_, handle)| handle)
+            .collect();
+
+        if handles.is_empty() {
+            return Ok(());
+        }
+
+        super::status(
+            Command::new("nix")
+                .arg("profile")
+                .arg("remove")
+                .args(handles),
+        )
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
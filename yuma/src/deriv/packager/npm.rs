@@ -0,0 +1,70 @@
+use crate::cmd;
+use crate::prelude::*;
+
+use std::{collections::HashMap, process::Command};
+
+use super::PackageBackend;
+
+/// Packages that ship with node itself and should never be pruned.
+const BUNDLED: &[&str] = &["npm", "corepack"];
+
+/// Globally installed node packages (`npm install --global`).
+#[derive(Debug, Default)]
+pub struct NpmPackager;
+
+#[derive(Debug, Deserialize)]
+struct NpmList {
+    #[serde(default)]
+    dependencies: HashMap<String, json::Value>,
+}
+
+impl PackageBackend for NpmPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let mut list_cmd = Command::new("npm");
+        list_cmd
+            .arg("list")
+            .arg("--global")
+            .arg("--depth=0")
+            .arg("--json");
+        let out = cmd::raw_output(&mut list_cmd)?;
+        // problems like unmet peer dependencies fail the command but the
+        // listing is still printed
+        match json::from_slice::<NpmList>(&out.stdout) {
+            Ok(list) => Ok(list.dependencies.into_keys().collect()),
+            Err(_) if !out.status.success() => {
+                Err(YumaError::command(&list_cmd, out.status, &out.stderr).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list_installed()?
+            .into_iter()
+            .filter(|name| !BUNDLED.contains(&name.as_str()))
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            Command::new("npm")
+                .arg("install")
+                .arg("--global")
+                .args(pkgs),
+        )
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            Command::new("npm")
+                .arg("uninstall")
+                .arg("--global")
+                .args(pkgs),
+        )
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -1,55 +1,468 @@
+use crate::cmd;
 use crate::prelude::*;
+use crate::prompt;
 
-use std::{collections::HashMap, fs, process::Command, sync::OnceLock};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs, io,
+    path::PathBuf,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
 
-use super::PackageBackend;
+use color_eyre::eyre::bail;
+
+use super::{
+    version_matches, InstallReason, PackageBackend, PackageInfo, Repo, SpecficName, Upgrade,
+};
+
+/// Where pacman keeps `IgnorePkg` which is how packages are held, and its
+/// repositories.
+pub const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// Surround the repositories that were added through a [`Repo`] so that the
+/// ones that came with the system are never touched.
+const REPOS_BEGIN: &str = "# BEGIN yuma repositories";
+const REPOS_END: &str = "# END yuma repositories";
+
+/// The name of a repository section and the lines under it.
+type Section = (String, Vec<String>);
 
 pub static PARU_NAME_MAP: OnceLock<HashMap<super::GenericName, super::SpecficName>> =
     OnceLock::new();
 
 #[derive(Debug, Default)]
-pub struct ParuPackager;
+pub struct ParuPackager {
+    config: Option<PathBuf>,
+    /// Set when a repository was added whose database is not synced yet.
+    /// Syncing it on its own would leave the system partially upgraded so
+    /// the next install does a full `-Syu` instead.
+    unsynced: AtomicBool,
+}
+
+impl ParuPackager {
+    /// Creates a packager that holds packages and adds repositories in the
+    /// given pacman config instead of [`PACMAN_CONF`].
+    pub fn with_config(config: impl Into<PathBuf>) -> Self {
+        Self {
+            config: Some(config.into()),
+            ..Default::default()
+        }
+    }
+
+    fn config(&self) -> PathBuf {
+        self.config
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(PACMAN_CONF))
+    }
+
+    /// Rewrites the config so exactly `held` is ignored. Other `IgnorePkg`
+    /// lines are merged into the first one.
+    fn set_held(&self, held: &BTreeSet<String>) -> Result<()> {
+        let path = self.config();
+        let conf = fs::read_to_string(&path)?;
+
+        let line = if held.is_empty() {
+            "#IgnorePkg   =".to_string()
+        } else {
+            let names: Vec<&str> = held.iter().map(String::as_str).collect();
+            format!("IgnorePkg = {}", names.join(" "))
+        };
+
+        let mut out = Vec::new();
+        let mut placed = false;
+        for l in conf.lines() {
+            let key = l
+                .trim_start_matches('#')
+                .split('=')
+                .next()
+                .unwrap_or("")
+                .trim();
+            if key != "IgnorePkg" {
+                out.push(l.to_string());
+            } else if !placed {
+                out.push(line.clone());
+                placed = true;
+            } else if l.starts_with('#') {
+                out.push(l.to_string());
+            }
+        }
+        if !placed {
+            let options = out.iter().position(|l| l.trim() == "[options]");
+            let Some(options) = options else {
+                bail!("No [options] section in {}", path.display());
+            };
+            out.insert(options + 1, line);
+        }
+
+        let mut conf = out.join("\n");
+        conf.push('\n');
+        // the config belongs to root so it is written through `tee`
+        cmd::output_with_stdin(super::elevated("tee").arg(&path), conf.as_bytes())?;
+        Ok(())
+    }
+
+    fn repos(&self) -> Result<Vec<Section>> {
+        Ok(split_repos(&fs::read_to_string(self.config())?).1)
+    }
+
+    /// Rewrites the config so exactly `repos` are managed by yuma. They go at
+    /// the end of the config when none were added before.
+    fn set_repos(&self, repos: &[Section]) -> Result<()> {
+        let path = self.config();
+        let (mut out, _, after) = split_repos(&fs::read_to_string(&path)?);
+
+        if !repos.is_empty() {
+            if out.last().is_some_and(|l| !l.trim().is_empty()) {
+                out.push(String::new());
+            }
+            out.push(REPOS_BEGIN.to_string());
+            for (name, lines) in repos {
+                out.push(format!("[{name}]"));
+                out.extend(lines.iter().cloned());
+            }
+            out.push(REPOS_END.to_string());
+        }
+        out.extend(after);
+
+        let mut conf = out.join("\n");
+        conf.push('\n');
+        cmd::output_with_stdin(super::elevated("tee").arg(&path), conf.as_bytes())?;
+        Ok(())
+    }
+
+    /// Upgrades from the repositories, checked against freshly synced
+    /// databases without syncing the ones the system uses.
+    fn repo_upgrades(&self) -> Result<Vec<Upgrade>> {
+        let mut checkupdates = query_cmd("checkupdates");
+        let out = cmd::raw_output(&mut checkupdates)?;
+        // exits with 2 when there is nothing to upgrade
+        if !out.status.success() && out.status.code() != Some(2) {
+            return Err(YumaError::command(&checkupdates, out.status, &out.stderr).into());
+        }
+        Ok(parse_upgrades(&String::from_utf8(out.stdout)?))
+    }
+
+    fn aur_upgrades(&self) -> Result<Vec<Upgrade>> {
+        // -Qua exits with an error when there is nothing to upgrade
+        let out = cmd::raw_output(query_cmd("paru").arg("-Qua"))?;
+        Ok(parse_upgrades(&String::from_utf8(out.stdout)?))
+    }
+}
 
 impl PackageBackend for ParuPackager {
-    fn list_leaves(&self) -> Vec<String> {
-        let stdout = Command::new("paru").arg("-Qqt").output().unwrap().stdout;
-        String::from_utf8(stdout)
-            .unwrap()
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("paru").arg("-Qqt"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("paru").arg("-Qq"))?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn install(&self, pkgs: Vec<String>) -> Result<()> {
+        let op = if self.unsynced.swap(false, Ordering::Relaxed) {
+            "-Syu"
+        } else {
+            "-S"
+        };
+        super::status(paru(op).arg("--needed").args(pkgs))
+    }
+
+    fn remove(&self, pkgs: Vec<String>) -> Result<()> {
+        super::status(paru("-Rns").args(pkgs))
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        let mut infos = parse_info(&super::output(query_cmd("paru").arg("-Qi"))?);
+
+        // -Qm exits with an error when nothing is foreign
+        let foreign = cmd::raw_output(query_cmd("paru").arg("-Qqm"))?.stdout;
+        let foreign: HashSet<&str> = std::str::from_utf8(&foreign)?.lines().collect();
+
+        // looks like `core linux 6.9.1-1 [installed]`
+        let sync = super::output(query_cmd("pacman").arg("-Sl"))?;
+        let repos: HashMap<&str, &str> = sync
             .lines()
-            .map(ToString::to_string)
-            .collect()
+            .filter(|line| line.contains("[installed"))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let repo = parts.next()?;
+                Some((parts.next()?, repo))
+            })
+            .collect();
+
+        for info in infos.iter_mut() {
+            info.repository = if foreign.contains(info.name.as_str()) {
+                Some("aur".into())
+            } else {
+                repos.get(info.name.as_str()).map(|repo| repo.to_string())
+            };
+        }
+        Ok(infos)
     }
 
-    fn list_installed(&self) -> Vec<String> {
-        let stdout = Command::new("paru").arg("-Qq").output().unwrap().stdout;
-        String::from_utf8(stdout)
-            .unwrap()
+    fn info(&self, name: &str) -> Result<Option<PackageInfo>> {
+        let local = cmd::raw_output(query_cmd("paru").arg("-Qi").arg(name))?;
+        let mut sync_cmd = query_cmd("paru");
+        sync_cmd.arg("-Si").arg(name);
+        let sync = cmd::raw_output(&mut sync_cmd)?;
+        let remote = sync
+            .status
+            .success()
+            .then(|| parse_info(&String::from_utf8_lossy(&sync.stdout)).pop())
+            .flatten();
+
+        if local.status.success() {
+            let Some(mut info) = parse_info(std::str::from_utf8(&local.stdout)?).pop() else {
+                return Ok(None);
+            };
+            info.repository = remote.and_then(|remote| remote.repository);
+            return Ok(Some(info));
+        }
+
+        if remote.is_none() && !String::from_utf8_lossy(&sync.stderr).contains("was not found") {
+            return Err(YumaError::command(&sync_cmd, sync.status, &sync.stderr).into());
+        }
+        Ok(remote)
+    }
+
+    fn search(&self, query: &str) -> Result<Vec<PackageInfo>> {
+        let mut search_cmd = query_cmd("paru");
+        search_cmd.arg("-Ss").arg(query);
+        let out = cmd::raw_output(&mut search_cmd)?;
+        // -Ss exits with 1 and says nothing when nothing matches, anything
+        // else is a real failure like a broken database
+        let no_matches = out.status.code() == Some(1) && out.stderr.trim_ascii().is_empty();
+        if !out.status.success() && !no_matches {
+            return Err(YumaError::command(&search_cmd, out.status, &out.stderr).into());
+        }
+        let stdout = String::from_utf8(out.stdout)?;
+
+        // looks like
+        // aur/paru 2.0.3-1 [+1234 ~5.00] [Installed]
+        //     Feature packed AUR helper
+        let mut hits: Vec<PackageInfo> = Vec::new();
+        for line in stdout.lines() {
+            if let Some(desc) = line.strip_prefix("    ") {
+                if let Some(hit) = hits.last_mut() {
+                    hit.description = Some(desc.trim().to_string());
+                }
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (Some(id), Some(version)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let Some((repo, name)) = id.split_once('/') else {
+                continue;
+            };
+            hits.push(PackageInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: None,
+                repository: Some(repo.to_string()),
+                installed_size: None,
+                reason: None,
+            });
+        }
+        Ok(hits)
+    }
+
+    fn install_version(&self, pkg: SpecficName, version: &str) -> Result<SpecficName> {
+        // pacman only has the latest version of everything so all we can do is
+        // make sure that is the one that was asked for
+        if let Some(installed) = self.info(&pkg)?.filter(|i| i.is_installed()) {
+            if version_matches(version, &installed.version) {
+                return Ok(pkg);
+            }
+        }
+
+        let stdout = super::output(query_cmd("paru").arg("-Si").arg(&pkg))?;
+        let Some(available) = parse_info(&stdout).pop() else {
+            bail!("{pkg} was not found");
+        };
+        if !version_matches(version, &available.version) {
+            bail!(
+                "paru can only install {pkg} {} which does not match {version}",
+                available.version
+            );
+        }
+
+        self.install(vec![pkg.clone()])?;
+        Ok(pkg)
+    }
+
+    fn hold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        let mut held: BTreeSet<String> = self.list_held()?.into_iter().collect();
+        let before = held.len();
+        held.extend(pkgs);
+        if held.len() == before {
+            return Ok(());
+        }
+        self.set_held(&held)
+    }
+
+    fn unhold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        let mut held: BTreeSet<String> = self.list_held()?.into_iter().collect();
+        let before = held.len();
+        held.retain(|name| !pkgs.contains(name));
+        if held.len() == before {
+            return Ok(());
+        }
+        self.set_held(&held)
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        let conf = match fs::read_to_string(self.config()) {
+            Ok(conf) => conf,
+            // nothing can be ignored without a config
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(conf
             .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| key.trim() == "IgnorePkg")
+            .flat_map(|(_, names)| names.split_whitespace())
             .map(ToString::to_string)
-            .collect()
+            .collect())
     }
 
-    fn install(&self, pkgs: Vec<String>) -> Result<()> {
-        Command::new("paru")
-            .arg("-S")
-            .arg("--needed")
-            // .arg("--yes")
-            .args(pkgs)
-            .spawn()?
-            .wait()?;
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        let mut upgrades = self.repo_upgrades()?;
+        upgrades.extend(self.aur_upgrades()?);
+        Ok(upgrades)
+    }
+
+    fn upgrade(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        if pkgs.is_empty() {
+            return Ok(());
+        }
+
+        // pacman only upgrades the repositories as a whole, leaving some
+        // out is a partial upgrade which breaks arch. Held packages are
+        // the one exception pacman knows how to deal with
+        let held = self.list_held()?;
+        let partial: Vec<String> = self
+            .repo_upgrades()?
+            .into_iter()
+            .map(|u| u.name)
+            .filter(|name| !pkgs.contains(name) && !held.contains(name))
+            .collect();
+        if !partial.is_empty() {
+            bail!(
+                "Not upgrading {:?} would leave a partial upgrade, hold them to keep them back",
+                partial
+            );
+        }
+
+        // aur packages are built on their own so they can be left out
+        let skipped: Vec<String> = self
+            .aur_upgrades()?
+            .into_iter()
+            .map(|u| u.name)
+            .filter(|name| !pkgs.contains(name))
+            .collect();
+
+        let mut cmd = paru("-Syu");
+        if !skipped.is_empty() {
+            cmd.arg("--ignore").arg(skipped.join(","));
+        }
+        super::status(&mut cmd)?;
+        self.unsynced.store(false, Ordering::Relaxed);
         Ok(())
     }
 
-    fn remove(&self, pkgs: Vec<String>) -> Result<()> {
-        Command::new("paru")
-            .arg("-Rns")
-            // .arg("--yes")
-            .args(pkgs)
-            .spawn()?
-            .wait()?;
+    fn list_repos(&self) -> Result<Vec<String>> {
+        Ok(self.repos()?.into_iter().map(|(name, _)| name).collect())
+    }
+
+    fn add_repo(&self, repo: &Repo) -> Result<()> {
+        let mut lines: Vec<String> = repo
+            .url
+            .iter()
+            .map(|url| format!("Server = {url}"))
+            .collect();
+        lines.extend(repo.options.iter().map(|(k, v)| format!("{k} = {v}")));
+        if lines.is_empty() {
+            bail!(
+                "The {} repository needs a url or an Include option",
+                repo.name
+            );
+        }
+
+        let section = (repo.name.clone(), lines);
+        let mut repos = self.repos()?;
+        match repos.iter_mut().find(|(name, _)| *name == repo.name) {
+            Some(existing) if *existing == section => return Ok(()),
+            Some(existing) => *existing = section,
+            None => repos.push(section),
+        }
+
+        // the key has to be trusted before the database can be synced
+        if let Some(key) = repo.key.as_ref() {
+            super::status(super::elevated("pacman-key").arg("--recv-keys").arg(key))?;
+            super::status(super::elevated("pacman-key").arg("--lsign-key").arg(key))?;
+        }
+        self.set_repos(&repos)?;
+        self.unsynced.store(true, Ordering::Relaxed);
         Ok(())
     }
 
+    fn remove_repos(&self, names: Vec<String>) -> Result<()> {
+        let mut repos = self.repos()?;
+        repos.retain(|(name, _)| !names.contains(name));
+        self.set_repos(&repos)
+    }
+
+    fn list_orphans(&self) -> Result<Vec<SpecficName>> {
+        // -Qdt exits with an error when there are no orphans
+        let out = cmd::raw_output(query_cmd("pacman").arg("-Qdtq"))?;
+        Ok(String::from_utf8(out.stdout)?
+            .lines()
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn removal_plan(&self, pkgs: &[SpecficName]) -> Result<Vec<SpecficName>> {
+        // pacman fails here when something still depends on the packages
+        let stdout = super::output(
+            query_cmd("pacman")
+                .arg("-Rns")
+                .arg("--print")
+                .arg("--print-format")
+                .arg("%n")
+                .args(pkgs),
+        )?;
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn protected(&self) -> Vec<SpecficName> {
+        super::protect::patterns(&[
+            "base",
+            "base-devel",
+            "linux",
+            "linux-lts",
+            "linux-zen",
+            "linux-hardened",
+            "linux-firmware",
+            "grub",
+            "efibootmgr",
+            "systemd",
+            "glibc",
+            "sudo",
+            "pacman",
+            "paru",
+            "paru-bin",
+        ])
+    }
+
     fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
         PARU_NAME_MAP
             .get_or_init(|| {
@@ -63,6 +476,114 @@ impl PackageBackend for ParuPackager {
     }
 }
 
+/// Splits a pacman config into the lines before the repositories managed by
+/// yuma, those repositories and the lines after them.
+fn split_repos(conf: &str) -> (Vec<String>, Vec<Section>, Vec<String>) {
+    let mut lines = conf.lines();
+    let before = lines
+        .by_ref()
+        .take_while(|l| l.trim() != REPOS_BEGIN)
+        .map(ToString::to_string)
+        .collect();
+
+    let mut repos: Vec<Section> = vec![];
+    for line in lines.by_ref().take_while(|l| l.trim() != REPOS_END) {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            repos.push((name.to_string(), vec![]));
+        } else if let Some((_, body)) = repos.last_mut().filter(|_| !line.is_empty()) {
+            body.push(line.to_string());
+        }
+    }
+
+    (before, repos, lines.map(ToString::to_string).collect())
+}
+
+/// Parses lines like `linux 6.9.1.arch1-1 -> 6.9.2.arch1-1` which have an
+/// ` [ignored]` at the end for held packages.
+fn parse_upgrades(stdout: &str) -> Vec<Upgrade> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let old = parts.next()?;
+            let new = parts.nth(1)?;
+            Some(Upgrade {
+                name: name.to_string(),
+                old: old.to_string(),
+                new: new.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A paru operation that changes the system, which paru confirms itself
+/// unless yuma runs unattended.
+fn paru(op: &str) -> Command {
+    let mut cmd = Command::new("paru");
+    cmd.arg(op);
+    if prompt::is_unattended() {
+        cmd.arg("--noconfirm");
+    }
+    cmd
+}
+
+/// A query whose output is meant to be parsed so it should not be translated.
+fn query_cmd(program: &str) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
+/// Parses the blocks of `Key : Value` lines printed by `-Qi` and `-Si`.
+fn parse_info(stdout: &str) -> Vec<PackageInfo> {
+    stdout
+        .split("\n\n")
+        .filter_map(|block| {
+            // values that span lines continue on lines starting with spaces
+            let fields: HashMap<&str, &str> = block
+                .lines()
+                .filter(|line| !line.starts_with(' '))
+                .filter_map(|line| line.split_once(" : "))
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .collect();
+
+            let optional = |key| {
+                fields
+                    .get(key)
+                    .filter(|v| **v != "None")
+                    .map(|v| v.to_string())
+            };
+
+            Some(PackageInfo {
+                name: fields.get("Name")?.to_string(),
+                version: fields.get("Version")?.to_string(),
+                description: optional("Description"),
+                repository: optional("Repository"),
+                installed_size: fields.get("Installed Size").and_then(|s| parse_size(s)),
+                reason: fields.get("Install Reason").map(|reason| {
+                    if reason.starts_with("Explicitly") {
+                        InstallReason::Explicit
+                    } else {
+                        InstallReason::Dependency
+                    }
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Turns sizes like `1.50 MiB` into bytes.
+fn parse_size(size: &str) -> Option<u64> {
+    let (num, unit) = size.split_once(' ')?;
+    let num: f64 = num.parse().ok()?;
+    let power = ["B", "KiB", "MiB", "GiB", "TiB"]
+        .iter()
+        .position(|u| *u == unit)?;
+    Some((num * 1024f64.powi(power as i32)).round() as u64)
+}
+
 //     let mut pac = main.pac.clone();
 //
 //     let mut aur = main.aur.clone();
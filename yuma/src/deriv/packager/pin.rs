@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+/// Constraints on the version of a package.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    /// The version to install. Anything starting with it counts so `20`
+    /// matches `20.11.1` but not `200.1`.
+    pub version: Option<String>,
+    /// Keeps the package from being upgraded.
+    pub hold: bool,
+}
+
+impl Stub for Pin {
+    fn stub() -> Self {
+        Self::default()
+    }
+}
+
+impl Pin {
+    pub fn is_none(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether an installed or available version satisfies this pin. Anything
+    /// matches when no version was asked for.
+    pub fn matches(&self, version: &str) -> bool {
+        self.version
+            .as_deref()
+            .is_none_or(|req| version_matches(req, version))
+    }
+}
+
+/// Whether `version` is `req` or starts with it followed by a separator. An
+/// epoch like the `1:` in `1:1.3.1-1` is ignored unless `req` has one too.
+pub fn version_matches(req: &str, version: &str) -> bool {
+    let version = match version.split_once(':') {
+        Some((_epoch, rest)) if !req.contains(':') => rest,
+        _ => version,
+    };
+    match version.strip_prefix(req) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['.', '-', '+', '~', '_']),
+        None => false,
+    }
+}
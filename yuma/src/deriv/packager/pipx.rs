@@ -0,0 +1,43 @@
+use crate::prelude::*;
+
+use std::{collections::HashMap, process::Command};
+
+use super::PackageBackend;
+
+/// Python applications installed into their own venvs by pipx.
+#[derive(Debug, Default)]
+pub struct PipxPackager;
+
+#[derive(Debug, Deserialize)]
+struct PipxList {
+    venvs: HashMap<String, json::Value>,
+}
+
+impl PackageBackend for PipxPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("pipx").arg("list").arg("--json"))?;
+        let list: PipxList = json::from_str(&stdout)?;
+        Ok(list.venvs.into_keys().collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        // every venv is something the user asked for
+        self.list_installed()
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(Command::new("pipx").arg("install").args(pkgs))
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        // unlike install, uninstall only takes a single package
+        for pkg in pkgs {
+            super::status(Command::new("pipx").arg("uninstall").arg(pkg))?;
+        }
+        Ok(())
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
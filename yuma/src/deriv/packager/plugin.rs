@@ -0,0 +1,136 @@
+//! Backends that live in their own executable. A plugin called `name` is a
+//! program called `freight-backend-<name>` on `PATH`, which makes it
+//! possible to write backends in any language.
+//!
+//! Every operation runs the plugin once with a single JSON request on stdin,
+//! the op and its arguments:
+//!
+//! ```json
+//! {"op":"list_installed"}
+//! {"op":"list_leaves"}
+//! {"op":"install","pkgs":["ripgrep","fd"]}
+//! {"op":"remove","pkgs":["fd"]}
+//! {"op":"resolve_name","name":"ripgrep"}
+//! ```
+//!
+//! The plugin answers with a single JSON object on stdout. Either `{"ok":
+//! ...}` holding a list of names for the list ops, `null` for install and
+//! remove and a name for `resolve_name`, or `{"error":"..."}` when it failed.
+//! Anything the plugin writes to stderr is shown to the user.
+
+use crate::cmd;
+use crate::prelude::*;
+
+use std::{
+    ffi::OsString,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::de::DeserializeOwned;
+
+use super::{detect::Detector, PackageBackend};
+
+/// What plugin executables are prefixed with.
+pub const PLUGIN_PREFIX: &str = "freight-backend-";
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request<'a> {
+    ListInstalled,
+    ListLeaves,
+    Install { pkgs: &'a [String] },
+    Remove { pkgs: &'a [String] },
+    ResolveName { name: &'a str },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response<T> {
+    Ok(T),
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct PluginPackager {
+    name: String,
+    dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl PluginPackager {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            dir: None,
+            envs: vec![],
+        }
+    }
+
+    /// Runs the executable in `dir` instead of looking for it on `PATH`.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Sets an environment variable for every run of the plugin.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// The executable for this plugin.
+    pub fn program(&self) -> String {
+        format!("{PLUGIN_PREFIX}{}", self.name)
+    }
+
+    /// Whether the executable for a plugin called `name` is on `PATH`.
+    pub fn exists(name: &str) -> bool {
+        Detector::new().has_program(&format!("{PLUGIN_PREFIX}{name}"))
+    }
+
+    fn call<T: DeserializeOwned>(&self, request: Request<'_>) -> Result<T> {
+        let program = self.program();
+        let request = json::to_vec(&request)?;
+        let mut command = match &self.dir {
+            Some(dir) => Command::new(dir.join(&program)),
+            None => Command::new(&program),
+        };
+        command
+            .envs(self.envs.iter().cloned())
+            .stderr(Stdio::inherit());
+        let stdout = cmd::output_with_stdin(&mut command, &request)?;
+
+        let response: Response<T> = json::from_str(&stdout)
+            .wrap_err_with(|| format!("{program} gave an invalid response"))?;
+        match response {
+            Response::Ok(value) => Ok(value),
+            Response::Error(msg) => Err(eyre!("{program}: {msg}")),
+        }
+    }
+}
+
+impl PackageBackend for PluginPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        self.call(Request::ListInstalled)
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.call(Request::ListLeaves)
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        self.call(Request::Install { pkgs: &pkgs })
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        self.call(Request::Remove { pkgs: &pkgs })
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        // there is no way to report errors here so keep the name as is
+        self.call(Request::ResolveName { name: &name.0 })
+            .unwrap_or(name.0)
+    }
+}
@@ -0,0 +1,18 @@
+/// Whether `name` is matched by any of `patterns`. A pattern ending in `*`
+/// matches every name starting with the rest of it, anything else has to
+/// match exactly.
+pub fn is_protected<S: AsRef<str>>(name: &str, patterns: &[S]) -> bool {
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.as_ref();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }
+    })
+}
+
+/// Turns a static list of patterns into what [`super::PackageBackend::protected`]
+/// returns.
+pub(crate) fn patterns(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(ToString::to_string).collect()
+}
@@ -0,0 +1,433 @@
+use crate::cmd;
+use crate::deriv::pkg::list::AsPkgList;
+use crate::prelude::*;
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::eyre::{ensure, eyre, WrapErr};
+use sha2::{Digest, Sha256};
+
+use super::PackageBackend;
+
+thread_local! {
+/// Every recipe that has been declared so far, looked up by package name.
+static RECIPES: RefCell<HashMap<String, Recipe>> = RefCell::default();
+}
+
+/// Where the source of a [`Recipe`] comes from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Source {
+    /// A checkout that already exists on this machine.
+    Path(PathBuf),
+    /// A repository that gets cloned. Without a rev the default branch is
+    /// followed.
+    Git { url: String, rev: Option<String> },
+}
+
+/// Instructions for building a package from source.
+///
+/// Both commands are run through `sh` from the root of the source with
+/// `PREFIX` set to where things should be installed. The files listed are
+/// relative to the prefix and are what gets deleted when the package is
+/// removed.
+///
+/// ```rust
+/// # use yuma::prelude::*;
+/// # use yuma::deriv::packager::{Recipe, Source};
+/// let mut ctx = ctx();
+/// # ctx.dry_run();
+///
+/// let st = Recipe::new(
+///     "st",
+///     Source::Git {
+///         url: "https://git.suckless.org/st".into(),
+///         rev: Some("0.9".into()),
+///     },
+/// )
+/// .build("make")
+/// .install("make PREFIX=\"$PREFIX\" install")
+/// .file("bin/st");
+///
+/// ctx.add(st);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Recipe {
+    pub name: String,
+    pub source: Source,
+    pub build: Option<String>,
+    pub install: Option<String>,
+    pub files: Vec<PathBuf>,
+    /// Overrides where things get installed, see [`RecipePackager::default_prefix`].
+    pub prefix: Option<PathBuf>,
+}
+
+impl Recipe {
+    pub fn new(name: impl Into<String>, source: Source) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            build: None,
+            install: None,
+            files: vec![],
+            prefix: None,
+        }
+    }
+
+    pub fn build(mut self, cmd: impl Into<String>) -> Self {
+        self.build = Some(cmd.into());
+        self
+    }
+
+    pub fn install(mut self, cmd: impl Into<String>) -> Self {
+        self.install = Some(cmd.into());
+        self
+    }
+
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    pub fn prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Makes the recipe known to all recipe packagers.
+    pub fn declare(self) -> Pkgs {
+        let prefix = self
+            .prefix
+            .clone()
+            .unwrap_or_else(RecipePackager::default_prefix);
+        let name = self.name.clone();
+
+        RECIPES.with(|recipes| recipes.borrow_mut().insert(name.clone(), self));
+
+        Pkgs {
+            names: vec![name],
+            packager: Packager::recipe(prefix),
+            pin: default(),
+        }
+    }
+
+    /// Makes sure removing the package can only ever delete files inside the
+    /// prefix.
+    fn check_files(&self) -> Result<()> {
+        for file in self.files.iter() {
+            ensure!(
+                file.components().all(|c| matches!(c, Component::Normal(_))),
+                "Files of recipe {} must be relative and stay inside the prefix: {}",
+                self.name,
+                file.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Fingerprint of the recipe so that editing it triggers a rebuild.
+    fn digest(&self) -> String {
+        let data = json::to_vec(self).unwrap();
+        Sha256::digest(data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+impl AsPkgList for Recipe {
+    fn list(self) -> Vec<Pkgs> {
+        vec![self.declare()]
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InstalledRecipe {
+    recipe: String,
+    revision: Option<String>,
+    files: Vec<PathBuf>,
+}
+
+/// Builds packages from [`Recipe`]s and installs them into a prefix. What was
+/// built from which revision is tracked in `<prefix>/share/yuma/recipes.json`
+/// and a package only gets rebuilt once either of them changes.
+#[derive(Debug)]
+pub struct RecipePackager {
+    prefix: PathBuf,
+}
+
+impl Default for RecipePackager {
+    fn default() -> Self {
+        Self::new(Self::default_prefix())
+    }
+}
+
+impl RecipePackager {
+    pub fn new(prefix: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// `~/.local`
+    pub fn default_prefix() -> PathBuf {
+        let home = env::var_os("HOME").unwrap_or_default();
+        Path::new(&home).join(".local")
+    }
+
+    fn state_dir(&self) -> PathBuf {
+        self.prefix.join("share").join("yuma")
+    }
+
+    fn db_path(&self) -> PathBuf {
+        self.state_dir().join("recipes.json")
+    }
+
+    fn read_db(&self) -> Result<BTreeMap<String, InstalledRecipe>> {
+        let path = self.db_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let f = fs::File::open(path)?;
+        Ok(json::from_reader(f)?)
+    }
+
+    fn write_db(&self, db: &BTreeMap<String, InstalledRecipe>) -> Result<()> {
+        fs::create_dir_all(self.state_dir())?;
+        let f = fs::File::create(self.db_path())?;
+        json::to_writer_pretty(f, db)?;
+        Ok(())
+    }
+
+    /// Makes sure the source is on disk and up to date returning where it is.
+    fn checkout(&self, recipe: &Recipe) -> Result<PathBuf> {
+        match &recipe.source {
+            Source::Path(path) => Ok(path.clone()),
+            Source::Git { url, rev } => {
+                let dir = self.state_dir().join("src").join(&recipe.name);
+                if dir.exists() {
+                    git(&dir, ["fetch", "--quiet", "--tags", "origin"])?;
+                } else {
+                    fs::create_dir_all(self.state_dir().join("src"))?;
+                    cmd::status(
+                        Command::new("git")
+                            .arg("clone")
+                            .arg("--quiet")
+                            .arg(url)
+                            .arg(&dir),
+                    )
+                    .wrap_err_with(|| format!("Failed to clone {url}"))?;
+                }
+
+                let commit = match rev {
+                    Some(rev) => Self::resolve(&dir, rev)?,
+                    None => "origin/HEAD".to_string(),
+                };
+                git(&dir, ["checkout", "--quiet", "--detach", &commit])?;
+                Ok(dir)
+            }
+        }
+    }
+
+    /// What `rev` is in a fetched checkout. Branches are looked up on the
+    /// remote since the local ones are never updated, followed by tags and
+    /// then commits.
+    fn resolve(dir: &Path, rev: &str) -> Result<String> {
+        let candidates = [
+            format!("origin/{rev}"),
+            format!("refs/tags/{rev}"),
+            rev.to_string(),
+        ];
+        candidates
+            .iter()
+            .find_map(|candidate| {
+                git(
+                    dir,
+                    [
+                        "rev-parse",
+                        "--verify",
+                        "--quiet",
+                        &format!("{candidate}^{{commit}}"),
+                    ],
+                )
+                .ok()
+            })
+            .ok_or_else(|| eyre!("{rev} is not a branch, tag or commit in {}", dir.display()))
+    }
+
+    /// The commit the source is at. Plain directories that are not a git
+    /// repository have no revision and only rebuild when the recipe changes.
+    fn revision(&self, src: &Path) -> Option<String> {
+        git(src, ["rev-parse", "HEAD"]).ok()
+    }
+
+    /// Compares against the source without touching the checkout, this is
+    /// only asked to find out what needs installing.
+    fn is_current(&self, recipe: &Recipe, installed: &InstalledRecipe) -> Result<bool> {
+        if recipe.digest() != installed.recipe {
+            return Ok(false);
+        }
+        let (url, rev) = match &recipe.source {
+            Source::Path(path) => return Ok(self.revision(path) == installed.revision),
+            Source::Git { url, rev } => (url, rev.as_deref().unwrap_or("HEAD")),
+        };
+
+        // annotated tags are listed twice, `^{}` is the commit they point at
+        let out = cmd::raw_output(
+            Command::new("git")
+                .arg("ls-remote")
+                .arg(url)
+                .arg(rev)
+                .arg(format!("{rev}^{{}}")),
+        )?;
+        if !out.status.success() {
+            log::warn!("Could not reach {url}, assuming {} is current", recipe.name);
+            return Ok(true);
+        }
+        let refs = String::from_utf8(out.stdout)?;
+        let refs: Vec<(&str, &str)> = refs
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .collect();
+        let remote = refs
+            .iter()
+            .find(|(_, name)| name.ends_with("^{}"))
+            .or(refs.first())
+            .map(|(commit, _)| *commit);
+
+        let installed = installed.revision.as_deref().unwrap_or_default();
+        Ok(match remote {
+            Some(commit) => installed == commit,
+            // a rev that isn't a ref on the remote has to be a commit
+            None => !installed.is_empty() && installed.starts_with(rev),
+        })
+    }
+
+    fn run(&self, src: &Path, script: &str) -> Result<()> {
+        cmd::status(
+            Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .current_dir(src)
+                .env("PREFIX", &self.prefix),
+        )
+        .wrap_err_with(|| format!("Recipe command failed: {script}"))
+    }
+}
+
+fn git<I, S>(dir: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let out = cmd::raw_output(Command::new("git").arg("-C").arg(dir).args(args))?;
+    ensure!(
+        out.status.success(),
+        "git failed in {}: {}",
+        dir.display(),
+        String::from_utf8_lossy(&out.stderr).trim()
+    );
+    Ok(String::from_utf8(out.stdout)?.trim().to_string())
+}
+
+impl PackageBackend for RecipePackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let mut installed = Vec::new();
+
+        // anything whose recipe or source moved on counts as not installed so
+        // the next update rebuilds it
+        for (name, record) in self.read_db()? {
+            let recipe = RECIPES.with(|recipes| recipes.borrow().get(&name).cloned());
+            let current = match recipe {
+                Some(recipe) => self.is_current(&recipe, &record)?,
+                None => true,
+            };
+            if current {
+                installed.push(name);
+            }
+        }
+
+        Ok(installed)
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(self.read_db()?.into_keys().collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let mut db = self.read_db()?;
+
+        for name in pkgs {
+            let recipe = RECIPES
+                .with(|recipes| recipes.borrow().get(&name).cloned())
+                .ok_or_else(|| YumaError::InvalidPackage { name: name.clone() })?;
+            recipe.check_files()?;
+
+            let src = self.checkout(&recipe)?;
+            info!("Building {} from {}", name, src.display());
+
+            if let Some(cmd) = &recipe.build {
+                self.run(&src, cmd)?;
+            }
+            if let Some(cmd) = &recipe.install {
+                self.run(&src, cmd)?;
+            }
+
+            let files: Vec<PathBuf> = recipe.files.iter().map(|f| self.prefix.join(f)).collect();
+            for file in files.iter() {
+                ensure!(
+                    file.exists(),
+                    "Recipe for {name} did not install {}",
+                    file.display()
+                );
+            }
+
+            // drop whatever the previous build left that this one no longer has
+            if let Some(old) = db.get(&name) {
+                for file in old.files.iter().filter(|f| !files.contains(f)) {
+                    if file.exists() {
+                        fs::remove_file(file)?;
+                    }
+                }
+            }
+
+            db.insert(
+                name,
+                InstalledRecipe {
+                    recipe: recipe.digest(),
+                    revision: self.revision(&src),
+                    files,
+                },
+            );
+            self.write_db(&db)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let mut db = self.read_db()?;
+
+        for name in pkgs {
+            let Some(installed) = db.remove(&name) else {
+                continue;
+            };
+            for file in installed.files {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+
+        self.write_db(&db)
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
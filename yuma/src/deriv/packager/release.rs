@@ -0,0 +1,363 @@
+use crate::cmd;
+use crate::deriv::pkg::list::AsPkgList;
+use crate::prelude::*;
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use color_eyre::eyre::{ensure, WrapErr};
+use sha2::{Digest, Sha256};
+
+use super::PackageBackend;
+
+thread_local! {
+/// Every release that has been declared so far. Packages only carry a name so
+/// this is where the backend looks up where to get them from.
+static RELEASES: RefCell<HashMap<String, Release>> = RefCell::default();
+}
+
+/// Name of the file in the bin dir that records which files we own.
+const DB_NAME: &str = ".yuma-releases.json";
+
+/// A tool that is shipped as a prebuilt archive, like most GitHub releases.
+///
+/// ```rust
+/// # use yuma::prelude::*;
+/// # use yuma::deriv::packager::Release;
+/// let mut ctx = ctx();
+/// # ctx.dry_run();
+///
+/// let rg = Release::new(
+///     "ripgrep",
+///     "https://github.com/BurntSushi/ripgrep/releases/download/14.1.0/ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+///     "f84757b07f425fe5cf11d87df6644691c644a5cd2348a2c670894272999d3ba7",
+/// )
+/// .bin("ripgrep-14.1.0-x86_64-unknown-linux-musl/rg");
+///
+/// ctx.add(rg);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Release {
+    pub name: String,
+    /// Where to get the archive from. Both `file://` and anything curl
+    /// understands work.
+    pub url: String,
+    /// Hex encoded sha256 of the archive.
+    pub sha256: String,
+    /// Paths of the binaries inside the archive. When empty the download
+    /// is the binary itself.
+    pub bins: Vec<PathBuf>,
+    /// Overrides where binaries get installed, see [`ReleasePackager::default_bin_dir`].
+    pub bin_dir: Option<PathBuf>,
+}
+
+impl Release {
+    pub fn new(name: impl Into<String>, url: impl Into<String>, sha256: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            sha256: sha256.into().to_lowercase(),
+            bins: vec![],
+            bin_dir: None,
+        }
+    }
+
+    pub fn bin(mut self, path: impl Into<PathBuf>) -> Self {
+        self.bins.push(path.into());
+        self
+    }
+
+    pub fn bin_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.bin_dir = Some(dir.into());
+        self
+    }
+
+    /// Makes the release known to all release packagers.
+    pub fn declare(self) -> Pkgs {
+        let bin_dir = self
+            .bin_dir
+            .clone()
+            .unwrap_or_else(ReleasePackager::default_bin_dir);
+        let name = self.name.clone();
+
+        RELEASES.with(|releases| releases.borrow_mut().insert(name.clone(), self));
+
+        Pkgs {
+            names: vec![name],
+            packager: Packager::release(bin_dir),
+            pin: default(),
+        }
+    }
+}
+
+impl AsPkgList for Release {
+    fn list(self) -> Vec<Pkgs> {
+        vec![self.declare()]
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InstalledRelease {
+    sha256: String,
+    files: Vec<PathBuf>,
+}
+
+/// Installs binaries from release archives into a single directory and keeps
+/// a record of the files so they can be removed again.
+#[derive(Debug)]
+pub struct ReleasePackager {
+    bin_dir: PathBuf,
+}
+
+impl Default for ReleasePackager {
+    fn default() -> Self {
+        Self::new(Self::default_bin_dir())
+    }
+}
+
+impl ReleasePackager {
+    pub fn new(bin_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            bin_dir: bin_dir.into(),
+        }
+    }
+
+    /// `~/.local/bin`
+    pub fn default_bin_dir() -> PathBuf {
+        let home = env::var_os("HOME").unwrap_or_default();
+        Path::new(&home).join(".local").join("bin")
+    }
+
+    fn db_path(&self) -> PathBuf {
+        self.bin_dir.join(DB_NAME)
+    }
+
+    fn read_db(&self) -> Result<BTreeMap<String, InstalledRelease>> {
+        let path = self.db_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let f = fs::File::open(path)?;
+        Ok(json::from_reader(f)?)
+    }
+
+    fn write_db(&self, db: &BTreeMap<String, InstalledRelease>) -> Result<()> {
+        fs::create_dir_all(&self.bin_dir)?;
+        let f = fs::File::create(self.db_path())?;
+        json::to_writer_pretty(f, db)?;
+        Ok(())
+    }
+
+    fn fetch(url: &str) -> Result<Vec<u8>> {
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(fs::read(path)?);
+        }
+
+        let out = cmd::raw_output(
+            Command::new("curl")
+                .arg("--fail")
+                .arg("--silent")
+                .arg("--show-error")
+                .arg("--location")
+                .arg(url),
+        )?;
+        ensure!(
+            out.status.success(),
+            "Failed to download {url}: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+        Ok(out.stdout)
+    }
+
+    /// Downloads, verifies and unpacks a single release returning the files
+    /// that were put into the bin dir. Only files in `owned`, the ones the
+    /// installed version of this release put there, are ever overwritten.
+    fn install_release(&self, release: &Release, owned: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let data = Self::fetch(&release.url)?;
+
+        let found: String = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        if found != release.sha256 {
+            return Err(YumaError::ChecksumMismatch {
+                name: release.name.clone(),
+                expected: release.sha256.clone(),
+                found,
+            }
+            .into());
+        }
+
+        // a bare binary needs no unpacking
+        let bins = if release.bins.is_empty() {
+            vec![PathBuf::from(&release.name)]
+        } else {
+            release.bins.clone()
+        };
+        let mut files = Vec::new();
+        for bin in bins.iter() {
+            let Some(file_name) = bin.file_name() else {
+                return Err(YumaError::InvalidPackage {
+                    name: bin.display().to_string(),
+                }
+                .into());
+            };
+            let dest = self.bin_dir.join(file_name);
+            ensure!(
+                owned.contains(&dest) || !dest.exists(),
+                "Not overwriting {} for {} as it wasn't installed by it",
+                dest.display(),
+                release.name
+            );
+            files.push(dest);
+        }
+
+        let work = env::temp_dir().join(format!("yuma-release-{}-{}", release.name, process::id()));
+        fs::create_dir_all(&work)?;
+        let res = self
+            .unpack(release, &data, &work)
+            .and_then(|out_dir| self.place(&out_dir, &bins, &files));
+        fs::remove_dir_all(&work)?;
+        res.map(|()| files)
+    }
+
+    /// Unpacks the archive returning the directory it was unpacked to.
+    fn unpack(&self, release: &Release, data: &[u8], work: &Path) -> Result<PathBuf> {
+        let out_dir = work.join("out");
+        fs::create_dir_all(&out_dir)?;
+        if release.bins.is_empty() {
+            fs::write(out_dir.join(&release.name), data)?;
+            return Ok(out_dir);
+        }
+
+        let archive_name = release.url.rsplit('/').next().unwrap_or("archive");
+        let archive = work.join(archive_name);
+        fs::write(&archive, data)?;
+
+        let mut unpack = if archive_name.ends_with(".zip") {
+            let mut unzip = Command::new("unzip");
+            unzip.arg("-q").arg(&archive).arg("-d").arg(&out_dir);
+            unzip
+        } else {
+            // tar figures out the compression on its own
+            let mut tar = Command::new("tar");
+            tar.arg("-xf").arg(&archive).arg("-C").arg(&out_dir);
+            tar
+        };
+        cmd::status(&mut unpack).wrap_err_with(|| format!("Failed to unpack {archive_name}"))?;
+        Ok(out_dir)
+    }
+
+    /// Copies the binaries next to where they go first and only moves them
+    /// into place once all of them made it, so a failure halfway leaves the
+    /// bin dir as it was.
+    fn place(&self, out_dir: &Path, bins: &[PathBuf], files: &[PathBuf]) -> Result<()> {
+        fs::create_dir_all(&self.bin_dir)?;
+
+        let mut staged = Vec::new();
+        let res = bins.iter().zip(files).try_for_each(|(bin, dest)| {
+            let file_name = dest.file_name().unwrap().to_string_lossy();
+            let tmp = self.bin_dir.join(format!(".{file_name}.yuma-new"));
+            staged.push(tmp.clone());
+            fs::copy(out_dir.join(bin), &tmp)?;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o755))?;
+            Ok(())
+        });
+        if let Err(e) = res {
+            for tmp in staged.iter().filter(|tmp| tmp.exists()) {
+                fs::remove_file(tmp)?;
+            }
+            return Err(e);
+        }
+
+        for (tmp, dest) in staged.iter().zip(files) {
+            fs::rename(tmp, dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl PackageBackend for ReleasePackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let db = self.read_db()?;
+
+        // a release whose checksum changed counts as not installed so that
+        // bumping the declaration upgrades it
+        Ok(RELEASES.with(|releases| {
+            let releases = releases.borrow();
+            db.into_iter()
+                .filter(|(name, installed)| {
+                    releases
+                        .get(name)
+                        .is_none_or(|release| release.sha256 == installed.sha256)
+                })
+                .map(|(name, _)| name)
+                .collect()
+        }))
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(self.read_db()?.into_keys().collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let mut db = self.read_db()?;
+
+        for name in pkgs {
+            let release = RELEASES
+                .with(|releases| releases.borrow().get(&name).cloned())
+                .ok_or_else(|| YumaError::InvalidPackage { name: name.clone() })?;
+
+            info!("Installing release {} from {}", name, release.url);
+            let owned = db.get(&name).map_or(&[][..], |old| &old.files[..]);
+            let files = self.install_release(&release, owned)?;
+
+            // clean up anything the old version had that this one does not
+            if let Some(old) = db.get(&name) {
+                for file in old.files.iter().filter(|f| !files.contains(f)) {
+                    if file.exists() {
+                        fs::remove_file(file)?;
+                    }
+                }
+            }
+
+            db.insert(
+                name,
+                InstalledRelease {
+                    sha256: release.sha256,
+                    files,
+                },
+            );
+            self.write_db(&db)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let mut db = self.read_db()?;
+
+        for name in pkgs {
+            let Some(installed) = db.remove(&name) else {
+                continue;
+            };
+            for file in installed.files {
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+
+        self.write_db(&db)
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+
+/// A third party source of packages like a pacman repository, a brew tap, an
+/// apt source or a flatpak remote. What is needed besides the name depends on
+/// the packager it is declared for.
+///
+/// ```rust
+/// use yuma::deriv::packager::Repo;
+///
+/// let chaotic = Repo::new("chaotic-aur")
+///     .key("3056513887B78AEB")
+///     .option("Include", "/etc/pacman.d/chaotic-mirrorlist");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Repo {
+    pub name: String,
+    pub url: Option<String>,
+    /// The key packages are signed with. A fingerprint for pacman, a url to
+    /// the key for apt and a key file for flatpak.
+    pub key: Option<String>,
+    /// Anything else the packager needs, like `Include` for pacman or the
+    /// `suite` for apt.
+    pub options: BTreeMap<String, String>,
+}
+
+impl Repo {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: None,
+            key: None,
+            options: BTreeMap::new(),
+        }
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+}
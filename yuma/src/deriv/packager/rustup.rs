@@ -0,0 +1,288 @@
+use crate::prelude::*;
+
+use std::{fmt, process::Command};
+
+use super::PackageBackend;
+
+/// Components every toolchain has that rustup refuses to remove.
+const REQUIRED: &[&str] = &["rustc", "cargo"];
+
+/// Manages rust toolchains along with their components and targets. Names
+/// encode what they refer to:
+///
+/// - `stable` is a toolchain
+/// - `stable/component/clippy` is a component of a toolchain
+/// - `stable/target/wasm32-unknown-unknown` is a target of a toolchain
+///
+/// Toolchains are installed with the minimal profile so every extra component
+/// has to be declared, otherwise it ends up as a leaf that gets pruned.
+#[derive(Debug, Default)]
+pub struct RustupPackager;
+
+#[derive(Debug, PartialEq, Eq)]
+enum RustupItem {
+    Toolchain(String),
+    Component { toolchain: String, name: String },
+    Target { toolchain: String, triple: String },
+}
+
+impl RustupItem {
+    fn parse(name: &str) -> Result<Self> {
+        let mut parts = name.splitn(3, '/');
+        let toolchain = parts.next().unwrap_or_default().to_string();
+        match (parts.next(), parts.next()) {
+            (None, None) => Ok(RustupItem::Toolchain(toolchain)),
+            (Some("component"), Some(name)) => Ok(RustupItem::Component {
+                toolchain,
+                name: name.to_string(),
+            }),
+            (Some("target"), Some(triple)) => Ok(RustupItem::Target {
+                toolchain,
+                triple: triple.to_string(),
+            }),
+            _ => Err(YumaError::InvalidPackage {
+                name: name.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    fn toolchain(&self) -> &str {
+        match self {
+            RustupItem::Toolchain(toolchain)
+            | RustupItem::Component { toolchain, .. }
+            | RustupItem::Target { toolchain, .. } => toolchain,
+        }
+    }
+}
+
+impl fmt::Display for RustupItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RustupItem::Toolchain(toolchain) => write!(f, "{toolchain}"),
+            RustupItem::Component { toolchain, name } => {
+                write!(f, "{toolchain}/component/{name}")
+            }
+            RustupItem::Target { toolchain, triple } => write!(f, "{toolchain}/target/{triple}"),
+        }
+    }
+}
+
+fn rustup<I, S>(args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    super::output(Command::new("rustup").args(args))
+}
+
+impl RustupPackager {
+    /// The triple rustup appends to toolchain and component names.
+    fn host(&self) -> Result<String> {
+        Ok(rustup(["show"])?
+            .lines()
+            .find_map(|line| line.strip_prefix("Default host:"))
+            .unwrap_or_default()
+            .trim()
+            .to_string())
+    }
+
+    /// Every installed item along with whether rustup would let us remove it.
+    fn items(&self) -> Result<Vec<(RustupItem, bool)>> {
+        let host = self.host()?;
+        let suffix = format!("-{host}");
+        let mut items = Vec::new();
+
+        for line in rustup(["toolchain", "list"])?.lines() {
+            // lines look like `stable-x86_64-unknown-linux-gnu (default)`
+            let Some(full) = line.split_whitespace().next() else {
+                continue;
+            };
+            if full == "no" {
+                // `no installed toolchains`
+                break;
+            }
+            let toolchain = full.strip_suffix(&suffix).unwrap_or(full).to_string();
+
+            for component in
+                rustup(["component", "list", "--installed", "--toolchain", full])?.lines()
+            {
+                let component = component.trim();
+                let component = component.strip_suffix(&suffix).unwrap_or(component);
+
+                let item = match component.strip_prefix("rust-std-") {
+                    Some(triple) => RustupItem::Target {
+                        toolchain: toolchain.clone(),
+                        triple: triple.to_string(),
+                    },
+                    // the host std is the bare `rust-std` after stripping
+                    None if component == "rust-std" => RustupItem::Target {
+                        toolchain: toolchain.clone(),
+                        triple: host.clone(),
+                    },
+                    None => RustupItem::Component {
+                        toolchain: toolchain.clone(),
+                        name: component.to_string(),
+                    },
+                };
+
+                let removable = match &item {
+                    RustupItem::Target { triple, .. } => *triple != host,
+                    RustupItem::Component { name, .. } => !REQUIRED.contains(&name.as_str()),
+                    RustupItem::Toolchain(_) => true,
+                };
+                items.push((item, removable));
+            }
+
+            items.push((RustupItem::Toolchain(toolchain), true));
+        }
+
+        Ok(items)
+    }
+}
+
+impl PackageBackend for RustupPackager {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(self
+            .items()?
+            .into_iter()
+            .map(|(item, _)| item.to_string())
+            .collect())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(self
+            .items()?
+            .into_iter()
+            .filter(|(_, removable)| *removable)
+            .map(|(item, _)| item.to_string())
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let items = pkgs
+            .iter()
+            .map(|name| RustupItem::parse(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        // toolchains have to exist before anything can be added to them
+        for item in items.iter() {
+            if let RustupItem::Toolchain(toolchain) = item {
+                super::status(
+                    Command::new("rustup")
+                        .args(["toolchain", "install", "--profile", "minimal"])
+                        .arg(toolchain),
+                )?;
+            }
+        }
+
+        for item in items.iter() {
+            match item {
+                RustupItem::Toolchain(_) => {}
+                RustupItem::Component { toolchain, name } => {
+                    super::status(Command::new("rustup").args([
+                        "component",
+                        "add",
+                        "--toolchain",
+                        toolchain,
+                        name,
+                    ]))?;
+                }
+                RustupItem::Target { toolchain, triple } => {
+                    super::status(Command::new("rustup").args([
+                        "target",
+                        "add",
+                        "--toolchain",
+                        toolchain,
+                        triple,
+                    ]))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        let items = pkgs
+            .iter()
+            .map(|name| RustupItem::parse(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        let removed_toolchains: Vec<&str> = items
+            .iter()
+            .filter_map(|item| match item {
+                RustupItem::Toolchain(toolchain) => Some(toolchain.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        for item in items.iter() {
+            // everything in a toolchain goes away with it
+            if removed_toolchains.contains(&item.toolchain()) {
+                continue;
+            }
+
+            match item {
+                RustupItem::Toolchain(_) => {}
+                RustupItem::Component { toolchain, name } => {
+                    super::status(Command::new("rustup").args([
+                        "component",
+                        "remove",
+                        "--toolchain",
+                        toolchain,
+                        name,
+                    ]))?;
+                }
+                RustupItem::Target { toolchain, triple } => {
+                    super::status(Command::new("rustup").args([
+                        "target",
+                        "remove",
+                        "--toolchain",
+                        toolchain,
+                        triple,
+                    ]))?;
+                }
+            }
+        }
+
+        if !removed_toolchains.is_empty() {
+            super::status(
+                Command::new("rustup")
+                    .args(["toolchain", "uninstall"])
+                    .args(removed_toolchains),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removing a toolchain takes everything installed in it along.
+    fn removal_plan(&self, pkgs: &[super::SpecficName]) -> Result<Vec<super::SpecficName>> {
+        let items = pkgs
+            .iter()
+            .map(|name| RustupItem::parse(name))
+            .collect::<Result<Vec<_>>>()?;
+        let toolchains: Vec<&str> = items
+            .iter()
+            .filter_map(|item| match item {
+                RustupItem::Toolchain(toolchain) => Some(toolchain.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let mut plan = pkgs.to_vec();
+        if toolchains.is_empty() {
+            return Ok(plan);
+        }
+        for (item, _) in self.items()? {
+            let name = item.to_string();
+            if toolchains.contains(&item.toolchain()) && !plan.contains(&name) {
+                plan.push(name);
+            }
+        }
+        Ok(plan)
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
@@ -0,0 +1,76 @@
+use crate::prelude::*;
+
+use super::{custom, detect::Detector, PackageInfo, PackagerType};
+
+/// The builtin backends that can search, along with the program they need.
+const SEARCHABLE: &[(&str, PackagerType)] = &[
+    ("paru", PackagerType::Paru),
+    ("brew", PackagerType::Brew),
+    ("apt-cache", PackagerType::Apt),
+    ("dnf", PackagerType::Dnf),
+    ("cargo", PackagerType::Cargo),
+];
+
+/// A package found by [`search`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// The packager to use when adding this package.
+    pub packager: PackagerType,
+    pub info: PackageInfo,
+}
+
+impl SearchHit {
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.info.description.as_deref()
+    }
+}
+
+/// Searches every packager available on this system, that is the builtin
+/// ones whose programs are on `PATH` and every registered custom backend.
+///
+/// Exact name matches come first followed by names containing the query and
+/// then everything else. A packager that fails to search is logged and
+/// skipped so one broken backend doesn't hide the others.
+pub fn search(query: &str) -> Vec<SearchHit> {
+    search_with(&Detector::new(), query)
+}
+
+/// Like [`search`] but the builtin packagers are the ones `detector` finds.
+pub fn search_with(detector: &Detector, query: &str) -> Vec<SearchHit> {
+    let builtin = SEARCHABLE
+        .iter()
+        .filter(|(program, _)| detector.has_program(program))
+        .map(|(_, ptype)| ptype.clone());
+    let custom = custom::registered().into_iter().map(PackagerType::Custom);
+
+    let mut hits = Vec::new();
+    for ptype in builtin.chain(custom) {
+        let pkgr = Packager::from(ptype.clone());
+        match pkgr.search(query) {
+            Ok(found) => hits.extend(found.into_iter().map(|info| SearchHit {
+                packager: ptype.clone(),
+                info,
+            })),
+            Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {}
+            Err(e) => log::warn!("Searching {ptype:?} failed: {e}"),
+        }
+    }
+
+    // the sort is stable so each group keeps the packager order
+    let query = query.to_lowercase();
+    hits.sort_by_key(|hit| {
+        let name = hit.info.name.to_lowercase();
+        if name == query {
+            0
+        } else if name.contains(&query) {
+            1
+        } else {
+            2
+        }
+    });
+    hits
+}
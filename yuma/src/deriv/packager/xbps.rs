@@ -0,0 +1,81 @@
+use crate::prelude::*;
+
+use std::process::Command;
+
+use super::PackageBackend;
+
+#[derive(Debug, Default)]
+pub struct XbpsPackager;
+
+/// Strips the version off of a package string as xbps prints it
+/// (`name-version_revision`).
+fn pkgname(pkgver: &str) -> &str {
+    pkgver.rsplit_once('-').map_or(pkgver, |(name, _)| name)
+}
+
+impl PackageBackend for XbpsPackager {
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("xbps-query").arg("--list-manual-pkgs"))?;
+        Ok(stdout
+            .lines()
+            .map(pkgname)
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let stdout = super::output(Command::new("xbps-query").arg("--list-pkgs"))?;
+        // lines look like `ii bash-5.2.21_1   GNU Bourne Again Shell`
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .map(pkgname)
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn install(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            super::elevated("xbps-install")
+                .arg("--sync")
+                .arg("--yes")
+                .args(pkgs),
+        )
+    }
+
+    fn remove(&self, pkgs: Vec<super::SpecficName>) -> Result<()> {
+        super::status(
+            super::elevated("xbps-remove")
+                .arg("--recursive")
+                .arg("--yes")
+                .args(pkgs),
+        )
+    }
+
+    fn removal_plan(&self, pkgs: &[super::SpecficName]) -> Result<Vec<super::SpecficName>> {
+        // lines look like `libfoo-1.2_1 remove x86_64 ...` and xbps refuses
+        // when something still depends on the packages
+        let stdout = super::output(
+            Command::new("xbps-remove")
+                .arg("--recursive")
+                .arg("--dry-run")
+                .args(pkgs),
+        )?;
+        Ok(stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pkgver = fields.next()?;
+                (fields.next()? == "remove").then(|| pkgname(pkgver).to_string())
+            })
+            .collect())
+    }
+
+    fn protected(&self) -> Vec<super::SpecficName> {
+        super::protect::patterns(&["base-system", "linux*", "grub*", "glibc", "xbps"])
+    }
+
+    fn resolve_name(&self, name: super::GenericName) -> super::SpecficName {
+        name.0
+    }
+}
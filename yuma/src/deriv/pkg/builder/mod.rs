@@ -4,12 +4,14 @@ pub use self::list::AsPkgBuilderList;
 
 use crate::prelude::*;
 
-use crate::deriv::packager::Packager;
+use crate::deriv::packager::{Packager, Pin};
 use std::env;
 
 #[derive(Debug, Default)]
 pub struct PkgBuilder {
-    names: Vec<String>,
+    /// Every name keeps its own pin so that merging builders doesn't spread
+    /// one package's version to the others.
+    names: Vec<(String, Pin)>,
     allowed_hostnames: Option<Vec<String>>,
     allowed_arches: Option<Vec<String>>,
     allowed_oss: Option<Vec<String>>,
@@ -17,7 +19,10 @@ pub struct PkgBuilder {
 }
 
 impl PkgBuilder {
-    pub(crate) fn build(self) -> Option<Pkgs> {
+    /// One set of packages per distinct pin, as [`Pkgs`] only has one.
+    /// Without a packager of its own `default` is used, or the detected one
+    /// when there is none.
+    pub(crate) fn build(self, default: Option<&Packager>) -> Vec<Pkgs> {
         // HACK: error handling here is a real goof
         let hostname = nix::unistd::gethostname().unwrap().into_string().unwrap();
         let arch = env::consts::ARCH.to_string();
@@ -27,26 +32,37 @@ impl PkgBuilder {
             .allowed_hostnames
             .is_some_and(|hosts| !hosts.contains(&hostname))
         {
-            return None;
+            return vec![];
         }
 
         if self
             .allowed_arches
             .is_some_and(|arches| !arches.contains(&arch))
         {
-            return None;
+            return vec![];
         }
 
         if self.allowed_oss.is_some_and(|oss| !oss.contains(&os)) {
-            return None;
+            return vec![];
         }
 
-        let packager = self.packager.unwrap_or_default();
-
-        Some(Pkgs {
-            names: self.names,
-            packager,
-        })
+        let packager = self
+            .packager
+            .or_else(|| default.cloned())
+            .unwrap_or_default();
+
+        let mut sets: Vec<Pkgs> = Vec::new();
+        for (name, pin) in self.names {
+            match sets.iter_mut().find(|set| set.pin == pin) {
+                Some(set) => set.names.push(name),
+                None => sets.push(Pkgs {
+                    names: vec![name],
+                    packager: packager.clone(),
+                    pin,
+                }),
+            }
+        }
+        sets
     }
 
     /// Configures the package to only be built in if the current os matches
@@ -84,6 +100,27 @@ impl PkgBuilder {
         self.packager = Some(packager);
         self
     }
+
+    /// Installs a version starting with `version` instead of the latest, so
+    /// `"nodejs".b().version("20")` keeps node at `20.x`. Backends that can't
+    /// install specific versions fail instead of installing the latest.
+    /// Packages that were given their own version before being merged into
+    /// this builder keep it.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        let version = version.into();
+        for (_, pin) in self.names.iter_mut() {
+            pin.version.get_or_insert_with(|| version.clone());
+        }
+        self
+    }
+
+    /// Keeps the package from being upgraded.
+    pub fn hold(mut self) -> Self {
+        for (_, pin) in self.names.iter_mut() {
+            pin.hold = true;
+        }
+        self
+    }
 }
 
 pub trait AsBuilder {
@@ -105,7 +142,7 @@ impl From<&str> for PkgBuilder {
 impl From<String> for PkgBuilder {
     fn from(value: String) -> Self {
         PkgBuilder {
-            names: vec![value],
+            names: vec![(value, Pin::default())],
             ..default()
         }
     }
@@ -123,7 +160,10 @@ impl<const N: usize> From<[String; N]> for PkgBuilder {
     fn from(value: [String; N]) -> Self {
         // PERF: we own the data so we dont need to copy
         PkgBuilder {
-            names: Vec::from(value),
+            names: value
+                .into_iter()
+                .map(|name| (name, Pin::default()))
+                .collect(),
             ..default()
         }
     }
@@ -177,7 +217,10 @@ impl From<&[&str]> for PkgBuilder {
         // PERF: None of the data here is owned so both the array and elements
         // need to be copied.
         PkgBuilder {
-            names: value.iter().map(|s| s.to_string()).collect(),
+            names: value
+                .iter()
+                .map(|s| (s.to_string(), Pin::default()))
+                .collect(),
             ..default()
         }
     }
@@ -186,9 +229,9 @@ impl From<&[&str]> for PkgBuilder {
 impl From<&[String]> for PkgBuilder {
     fn from(value: &[String]) -> Self {
         // PERF: As with all the other data beind refs we don't own it so we
-        // have to copy (which is what [`slice::to_vec`] does).
+        // have to clone every name.
         PkgBuilder {
-            names: value.to_vec(),
+            names: value.iter().map(|s| (s.clone(), Pin::default())).collect(),
             ..default()
         }
     }
@@ -1,15 +1,16 @@
-use requestty::Question;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use color_eyre::eyre::ensure;
+use color_eyre::eyre::{ensure, WrapErr};
 use serde::{Deserialize, Serialize};
 use stub::Stub;
 
-use crate::deriv::packager::SpecficName;
+use crate::deriv::packager::{is_protected, version_matches, Packager, Pin, Repo, SpecficName};
 use crate::prelude::*;
+use crate::prompt::{self, RunMode};
 
 use super::{
-    builder::{AsPkgBuilderList, PkgBuilder},
+    builder::AsPkgBuilderList,
+    upgrade::{UpgradePlan, UpgradePolicy},
     Pkgs,
 };
 
@@ -17,26 +18,317 @@ use super::{
 pub(crate) struct PackagerDerivation {
     pkgr: Packager,
     enabled: Vec<SpecficName>,
-    prunable: HashSet<SpecficName>,
+    /// The leaves from before this derivation installed anything. They are
+    /// queried when it is first installed, even if that is declined, rather
+    /// than when packages are added so that adding them can't fail.
+    prunable: Option<HashSet<SpecficName>>,
+    /// Version constraints and holds of the enabled packages that have any.
+    #[serde(default)]
+    pins: HashMap<SpecficName, Pin>,
+    /// Everything the config asked for. Unlike `enabled` this is kept after
+    /// installing.
+    #[serde(default)]
+    declared: HashSet<SpecficName>,
+    /// Repositories that have to exist before anything is installed.
+    #[serde(default)]
+    repos: Vec<Repo>,
 }
 
 impl PackagerDerivation {
     pub fn new(pkgs: Pkgs) -> Self {
-        let prunable = pkgs.packager.list_leaves().into_iter().collect();
+        let mut deriv = Self::empty(pkgs.packager.clone());
+        deriv.add(pkgs).unwrap();
+        deriv
+    }
+
+    fn empty(pkgr: Packager) -> Self {
         Self {
-            pkgr: pkgs.packager,
-            enabled: pkgs.names,
-            prunable,
+            pkgr,
+            enabled: vec![],
+            prunable: None,
+            pins: HashMap::new(),
+            declared: HashSet::new(),
+            repos: vec![],
         }
     }
 
+    fn prunable(&mut self) -> Result<&mut HashSet<SpecficName>> {
+        let prunable = match self.prunable.take() {
+            Some(prunable) => prunable,
+            None => self.pkgr.list_leaves()?.into_iter().collect(),
+        };
+        Ok(self.prunable.insert(prunable))
+    }
+
     pub fn add(&mut self, pkgs: Pkgs) -> Result<()> {
         ensure!(
             pkgs.packager == self.pkgr,
             "Packager does not match this pakager."
         );
 
-        self.enabled.extend(pkgs.names);
+        for name in pkgs.names {
+            let (name, version) = self.pkgr.split_version(name);
+            let mut pin = pkgs.pin.clone();
+            if version.is_some() {
+                pin.version = version;
+            }
+
+            if !pin.is_none() {
+                self.pins.insert(name.clone(), pin);
+            }
+            self.declared.insert(name.clone());
+            self.enabled.push(name);
+        }
+        Ok(())
+    }
+
+    /// The names the packages with a version pin are installed as, see
+    /// [`PackageBackend::pinned_name`].
+    ///
+    /// [`PackageBackend::pinned_name`]: crate::deriv::packager::PackageBackend::pinned_name
+    fn pinned_names(&self) -> Result<HashMap<SpecficName, SpecficName>> {
+        self.pins
+            .iter()
+            .filter_map(|(name, pin)| Some((name, pin.version.as_deref()?)))
+            .map(|(name, version)| {
+                let pinned = self
+                    .pkgr
+                    .pinned_name(name, version)
+                    .wrap_err_with(|| format!("Invalid pin for {name}"))?;
+                Ok((name.clone(), pinned))
+            })
+            .collect()
+    }
+
+    /// Holds `held` and releases the declared packages that are held but
+    /// no longer asked to be. Holds on anything else were not made by yuma
+    /// so they are left alone.
+    fn sync_holds(
+        &self,
+        held: Vec<SpecficName>,
+        pinned: &HashMap<SpecficName, SpecficName>,
+    ) -> Result<()> {
+        let current = match self.pkgr.list_held() {
+            Ok(current) => current,
+            Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {
+                if held.is_empty() {
+                    return Ok(());
+                }
+                return self.pkgr.hold(held);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let wanted: HashSet<&SpecficName> = self
+            .pins
+            .iter()
+            .filter(|(_, pin)| pin.hold)
+            .map(|(name, _)| pinned.get(name).unwrap_or(name))
+            .collect();
+        let released: Vec<SpecficName> = current
+            .iter()
+            .filter(|name| self.declared.contains(*name) && !wanted.contains(name))
+            .cloned()
+            .collect();
+        if !released.is_empty() {
+            self.pkgr.unhold(released)?;
+        }
+
+        let missing: Vec<SpecficName> = held
+            .into_iter()
+            .filter(|name| !current.contains(name))
+            .collect();
+        if !missing.is_empty() {
+            self.pkgr.hold(missing)?;
+        }
+        Ok(())
+    }
+
+    fn add_repo(&mut self, repo: Repo) {
+        match self.repos.iter_mut().find(|r| r.name == repo.name) {
+            Some(existing) => *existing = repo,
+            None => self.repos.push(repo),
+        }
+    }
+
+    /// Adds the declared repositories that are missing and updates the rest.
+    /// Returns false when adding them was declined.
+    fn ensure_repos(&self, mode: &RunMode) -> Result<bool> {
+        if self.repos.is_empty() {
+            return Ok(true);
+        }
+
+        let existing = self.pkgr.list_repos()?;
+        let missing: Vec<&str> = self
+            .repos
+            .iter()
+            .filter(|repo| !existing.contains(&repo.name))
+            .map(|repo| repo.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            let yes = prompt::confirm(
+                mode,
+                "add_repos",
+                format!("Add repositories {:?} ?", missing),
+            )?;
+            if !yes {
+                return Ok(false);
+            }
+        }
+
+        for repo in self.repos.iter() {
+            self.pkgr
+                .add_repo(repo)
+                .wrap_err_with(|| format!("Failed to add repository {}", repo.name))?;
+        }
+        Ok(true)
+    }
+
+    /// Removes `prunable` along with whatever that takes with it. Leaves that
+    /// would take declared or protected packages are kept and reported.
+    fn prune_leaves(
+        &self,
+        prunable: HashSet<SpecficName>,
+        patterns: &[String],
+        mode: &RunMode,
+    ) -> Result<()> {
+        // declared packages that were already installed are still leaves
+        let mut prunable: Vec<SpecficName> = prunable
+            .into_iter()
+            .filter(|name| !self.declared.contains(name))
+            .collect();
+        prunable.sort();
+
+        let (protected, prunable): (Vec<_>, Vec<_>) = prunable
+            .into_iter()
+            .partition(|name| is_protected(name, patterns));
+        if !protected.is_empty() {
+            log::warn!("Not removing protected packages: {:?}", protected);
+        }
+        if prunable.is_empty() {
+            return Ok(());
+        }
+
+        let Some((prunable, plan)) = self.checked_removal(prunable, patterns) else {
+            return Ok(());
+        };
+
+        let extra: Vec<&SpecficName> = plan
+            .iter()
+            .filter(|name| !prunable.contains(name))
+            .collect();
+        let message = if extra.is_empty() {
+            format!("Remove {:?} ?", prunable)
+        } else {
+            format!("Remove {:?} along with {:?} ?", prunable, extra)
+        };
+        let yes = prompt::confirm(mode, "remove", message)?;
+
+        if yes {
+            // held packages can't be removed on some backends and pacman
+            // would keep ignoring them after
+            match self.pkgr.list_held() {
+                Ok(held) => {
+                    let released: Vec<SpecficName> = held
+                        .into_iter()
+                        .filter(|name| plan.contains(name))
+                        .collect();
+                    if !released.is_empty() {
+                        self.pkgr.unhold(released)?;
+                    }
+                }
+                Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {}
+                Err(e) => return Err(e),
+            }
+            self.pkgr.remove(prunable)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the packages whose removal would take declared or protected
+    /// packages with it, or can't be planned at all, and reports them.
+    /// Returns the rest along with everything removing them takes, or
+    /// nothing when none are left.
+    fn checked_removal(
+        &self,
+        mut pkgs: Vec<SpecficName>,
+        patterns: &[String],
+    ) -> Option<(Vec<SpecficName>, Vec<SpecficName>)> {
+        // a single package that can't go shouldn't keep the rest around, so
+        // when removing them together fails the ones at fault are dropped
+        loop {
+            match self.removal_plan(&pkgs, patterns) {
+                Ok(plan) => return Some((pkgs, plan)),
+                Err(e) if pkgs.len() == 1 => {
+                    log::error!("Not removing {:?}: {e}", pkgs);
+                    return None;
+                }
+                Err(e) => {
+                    let before = pkgs.len();
+                    pkgs.retain(|name| {
+                        match self.removal_plan(std::slice::from_ref(name), patterns) {
+                            Ok(_) => true,
+                            Err(e) => {
+                                log::error!("Not removing {name}: {e}");
+                                false
+                            }
+                        }
+                    });
+                    if pkgs.is_empty() {
+                        return None;
+                    }
+                    // each is fine alone but not all of them together
+                    if pkgs.len() == before {
+                        log::error!("Not removing {:?}: {e}", pkgs);
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Everything removing `pkgs` would take with it, failing when that
+    /// includes declared or protected packages.
+    fn removal_plan(&self, pkgs: &[SpecficName], patterns: &[String]) -> Result<Vec<SpecficName>> {
+        let plan = self.pkgr.removal_plan(pkgs)?;
+        let wanted: Vec<&SpecficName> = plan
+            .iter()
+            .filter(|name| self.declared.contains(*name) || is_protected(name, patterns))
+            .collect();
+        ensure!(wanted.is_empty(), "it would also remove {:?}", wanted);
+        Ok(plan)
+    }
+
+    /// Removes the repositories this packager added that aren't declared
+    /// anymore.
+    fn prune_repos(&self, mode: &RunMode) -> Result<()> {
+        let managed = match self.pkgr.list_repos() {
+            Ok(managed) => managed,
+            Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {
+                return Ok(());
+            }
+            Err(e) => {
+                log::error!("Not removing repositories: {e}");
+                return Ok(());
+            }
+        };
+        let undeclared: Vec<String> = managed
+            .into_iter()
+            .filter(|name| !self.repos.iter().any(|repo| repo.name == *name))
+            .collect();
+        if undeclared.is_empty() {
+            return Ok(());
+        }
+
+        let yes = prompt::confirm(
+            mode,
+            "remove_repos",
+            format!("Remove repositories {:?} ?", undeclared),
+        )?;
+
+        if yes {
+            self.pkgr.remove_repos(undeclared)?;
+        }
         Ok(())
     }
 }
@@ -44,6 +336,13 @@ impl PackagerDerivation {
 #[derive(Debug, Default, Serialize, Deserialize, Stub)]
 pub struct Packages {
     backends: Vec<PackagerDerivation>,
+    /// Never pruned from any packager, on top of what each backend protects
+    /// on its own.
+    #[serde(default)]
+    protected: Vec<String>,
+    /// Whether orphaned dependencies are removed after pruning.
+    #[serde(default)]
+    clean_orphans: bool,
 }
 
 impl Packages {
@@ -62,52 +361,269 @@ impl Packages {
         }
     }
 
-    pub(crate) fn install(&mut self) -> Result<()> {
+    pub(crate) fn add_repo(&mut self, pkgr: Packager, repo: Repo) {
+        match self.backends.iter_mut().find(|deriv| deriv.pkgr == pkgr) {
+            Some(deriv) => deriv.add_repo(repo),
+            None => {
+                let mut deriv = PackagerDerivation::empty(pkgr);
+                deriv.add_repo(repo);
+                self.backends.push(deriv);
+            }
+        }
+    }
+
+    pub(crate) fn install(&mut self, mode: &RunMode) -> Result<()> {
         for deriv in self.backends.iter_mut() {
-            let mut enabled: Vec<SpecficName> = deriv.enabled.drain(..).collect();
+            let repos_ready = deriv.ensure_repos(mode)?;
+            // the leaves from before anything is installed are what prune
+            // compares against, whatever the answer below is
+            deriv.prunable()?;
+            let enabled: Vec<SpecficName> = deriv.enabled.drain(..).collect();
+
+            // what pinned packages are installed as is declared too, so it
+            // isn't pruned even when installing is declined
+            let pinned = deriv.pinned_names()?;
+            deriv.declared.extend(pinned.values().cloned());
+
+            // the packages may only exist in the repositories that were refused
+            if !repos_ready {
+                log::warn!("Not installing {:?} without their repositories", enabled);
+                continue;
+            }
+
+            // packages are held after installing so they need to be known
+            // before the already installed ones are dropped
+            let held: Vec<SpecficName> = enabled
+                .iter()
+                .filter(|name| deriv.pins.get(*name).is_some_and(|pin| pin.hold))
+                .map(|name| pinned.get(name).unwrap_or(name).clone())
+                .collect();
+
+            // packages with a version are handed to the backend unless it
+            // says a matching version is installed already
+            let (versioned, mut enabled): (Vec<_>, Vec<_>) =
+                enabled.into_iter().partition(|name| {
+                    deriv
+                        .pins
+                        .get(name)
+                        .is_some_and(|pin| pin.version.is_some())
+                });
+            let mut versioned: Vec<(SpecficName, String)> = versioned
+                .into_iter()
+                .map(|name| {
+                    let version = deriv.pins[&name].version.clone().unwrap();
+                    (name, version)
+                })
+                .collect();
+            if !versioned.is_empty() {
+                match deriv.pkgr.installed_info() {
+                    Ok(infos) => versioned.retain(|(name, version)| {
+                        !infos.iter().any(|info| {
+                            info.name == pinned[name] && version_matches(version, &info.version)
+                        })
+                    }),
+                    Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {}
+                    Err(e) => return Err(e),
+                }
+            }
 
-            let installed = deriv.pkgr.list_installed();
+            let installed = deriv.pkgr.list_installed()?;
             // only install packages that are not already installed
             enabled.retain(|name| !installed.contains(name));
 
-            let q = Question::confirm("install")
-                .message(format!("Install {:?} ?", &enabled))
-                .default(false)
-                .build();
-
-            let requestty::Answer::Bool(yes) = requestty::prompt_one(q)? else {
-                unreachable!()
-            };
+            let shown: Vec<String> = enabled
+                .iter()
+                .cloned()
+                .chain(
+                    versioned
+                        .iter()
+                        .map(|(name, version)| format!("{name} {version}")),
+                )
+                .collect();
+            // there is nothing to ask when everything is installed already
+            let yes = shown.is_empty()
+                || prompt::confirm(mode, "install", format!("Install {:?} ?", shown))?;
 
             if yes {
                 // remove packages about to be installed from prunable list
+                let prunable = deriv.prunable()?;
                 for name in enabled.iter() {
-                    deriv.prunable.remove(name);
+                    prunable.remove(name);
+                }
+
+                if !enabled.is_empty() {
+                    deriv.pkgr.install(enabled)?;
                 }
 
-                deriv.pkgr.install(enabled)?;
+                for (name, version) in versioned {
+                    let actual = deriv
+                        .pkgr
+                        .install_version(name.clone(), &version)
+                        .wrap_err_with(|| format!("Failed to install {name} {version}"))?;
+
+                    let prunable = deriv.prunable()?;
+                    prunable.remove(&name);
+                    prunable.remove(&actual);
+                }
+
+                deriv
+                    .sync_holds(held, &pinned)
+                    .wrap_err("Failed to hold packages")?;
             }
         }
 
         Ok(())
     }
 
-    pub(crate) fn prune(&mut self) -> Result<()> {
-        for deriv in self.backends.drain(..) {
-            let q = Question::confirm("remove")
-                .message(format!("Remove {:?} ?", deriv.prunable))
-                .default(false)
-                .build();
+    pub(crate) fn upgrade_plan(&self, policy: UpgradePolicy) -> Result<UpgradePlan> {
+        let mut plan = UpgradePlan::default();
+        for deriv in self.backends.iter() {
+            let upgrades = match deriv.pkgr.list_upgrades() {
+                Ok(upgrades) => upgrades,
+                Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {
+                    log::info!("Skipping {:?}: {e}", deriv.pkgr.packager_type());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            // upgrades are for what pinned packages are installed as
+            let pinned = deriv.pinned_names()?;
+            let mut pins: HashMap<&SpecficName, &Pin> = deriv.pins.iter().collect();
+            for (name, installed_as) in pinned.iter() {
+                pins.insert(installed_as, &deriv.pins[name]);
+            }
+            let declared = |name: &SpecficName| {
+                deriv.declared.contains(name) || pinned.values().any(|n| n == name)
+            };
+
+            let mut held: HashSet<SpecficName> = HashSet::new();
+            if policy != UpgradePolicy::All {
+                match deriv.pkgr.list_held() {
+                    Ok(names) => held.extend(names),
+                    Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {}
+                    Err(e) => return Err(e),
+                }
+                let holding = pins.iter().filter(|(_, pin)| pin.hold);
+                held.extend(holding.map(|(name, _)| (*name).clone()));
+            }
+
+            let upgrades = upgrades
+                .into_iter()
+                .filter(|u| {
+                    let pin = pins.get(&u.name);
+                    match policy {
+                        UpgradePolicy::All => true,
+                        UpgradePolicy::Declared => {
+                            declared(&u.name)
+                                && !held.contains(&u.name)
+                                && pin.is_none_or(|pin| pin.matches(&u.new))
+                        }
+                        UpgradePolicy::ExcludeHeld => {
+                            !held.contains(&u.name) && pin.is_none_or(|pin| pin.version.is_none())
+                        }
+                    }
+                })
+                .collect();
+            plan.push(deriv.pkgr.clone(), upgrades);
+        }
+        Ok(plan)
+    }
+
+    pub(crate) fn upgrade(&mut self, policy: UpgradePolicy, mode: &RunMode) -> Result<()> {
+        let plan = self.upgrade_plan(policy)?;
+        if plan.is_empty() {
+            log::info!("Nothing to upgrade.");
+            return Ok(());
+        }
+
+        let shown: Vec<String> = plan
+            .upgrades()
+            .map(|(_, u)| format!("{} {} -> {}", u.name, u.old, u.new))
+            .collect();
+        let yes = prompt::confirm(mode, "upgrade", format!("Upgrade {:?} ?", shown))?;
+
+        if yes {
+            plan.apply()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn protect<I, S>(&mut self, names: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.protected.extend(names.into_iter().map(Into::into));
+    }
+
+    pub(crate) fn clean_orphans(&mut self) {
+        self.clean_orphans = true;
+    }
+
+    pub(crate) fn prune(&mut self, mode: &RunMode) -> Result<()> {
+        for deriv in self.backends.iter_mut() {
+            // install never ran so there is nothing to compare against
+            let Some(prunable) = deriv.prunable.take() else {
+                continue;
+            };
 
-            let requestty::Answer::Bool(yes) = requestty::prompt_one(q)? else {
-                unreachable!()
+            let mut patterns = deriv.pkgr.protected();
+            patterns.extend(self.protected.iter().cloned());
+            deriv.prune_leaves(prunable, &patterns, mode)?;
+            deriv.prune_repos(mode)?;
+        }
+
+        // pruning leaves is what orphans most dependencies so this goes last
+        if self.clean_orphans {
+            self.remove_orphans(mode)?;
+        }
+        self.backends.clear();
+        Ok(())
+    }
+
+    fn remove_orphans(&self, mode: &RunMode) -> Result<()> {
+        for deriv in self.backends.iter() {
+            let orphans = match deriv.pkgr.list_orphans() {
+                Ok(orphans) => orphans,
+                Err(e) if matches!(e.downcast_ref(), Some(YumaError::Unsupported { .. })) => {
+                    log::info!("Skipping {:?}: {e}", deriv.pkgr.packager_type());
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("Not removing orphans: {e}");
+                    continue;
+                }
             };
 
+            let mut patterns = deriv.pkgr.protected();
+            patterns.extend(self.protected.iter().cloned());
+            let orphans: Vec<SpecficName> = orphans
+                .into_iter()
+                .filter(|name| !deriv.declared.contains(name) && !is_protected(name, &patterns))
+                .collect();
+            if orphans.is_empty() {
+                continue;
+            }
+            // removing an orphan can still take declared packages with it
+            let Some((orphans, plan)) = deriv.checked_removal(orphans, &patterns) else {
+                continue;
+            };
+
+            let extra: Vec<&SpecficName> =
+                plan.iter().filter(|name| !orphans.contains(name)).collect();
+            let message = if extra.is_empty() {
+                format!("Remove orphaned dependencies {:?} ?", orphans)
+            } else {
+                format!(
+                    "Remove orphaned dependencies {:?} along with {:?} ?",
+                    orphans, extra
+                )
+            };
+            let yes = prompt::confirm(mode, "remove_orphans", message)?;
+
             if yes {
-                deriv
-                    .pkgr
-                    .remove(deriv.prunable.into_iter().collect())
-                    .unwrap();
+                deriv.pkgr.remove_orphans(orphans)?;
             }
         }
         Ok(())
@@ -116,13 +632,29 @@ impl Packages {
 
 pub trait AsPkgList {
     fn list(self) -> Vec<Pkgs>;
+
+    /// Like [`AsPkgList::list`] but packages that don't name a packager get
+    /// `default` instead of the detected one.
+    fn list_with_default(self, _default: &Packager) -> Vec<Pkgs>
+    where
+        Self: Sized,
+    {
+        self.list()
+    }
 }
 
 impl<B: AsPkgBuilderList> AsPkgList for B {
     fn list(self) -> Vec<Pkgs> {
         self.list()
             .into_iter()
-            .flat_map(PkgBuilder::build)
+            .flat_map(|b| b.build(None))
+            .collect()
+    }
+
+    fn list_with_default(self, default: &Packager) -> Vec<Pkgs> {
+        self.list()
+            .into_iter()
+            .flat_map(|b| b.build(Some(default)))
             .collect()
     }
 }
@@ -1,15 +1,19 @@
 use serde::{Deserialize, Serialize};
 use stub::Stub;
 
-use super::packager::{Packager, PackagerType, SpecficName};
+use super::packager::{Packager, PackagerType, Pin, SpecficName};
 
 pub mod builder;
 pub mod list;
+pub mod upgrade;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Stub)]
 pub struct Pkgs {
     pub names: Vec<SpecficName>,
     pub packager: Packager,
+    /// Applies to every name in the set.
+    #[serde(default)]
+    pub pin: Pin,
 }
 
 impl Pkgs {
@@ -22,6 +26,7 @@ impl Pkgs {
         Self {
             names: names.collect(),
             packager,
+            pin: Pin::default(),
         }
     }
 
@@ -43,10 +48,15 @@ impl Pkgs {
         self.packager = backend.into();
     }
 
+    pub fn pin(&mut self, pin: Pin) {
+        self.pin = pin;
+    }
+
     pub fn from_names<const N: usize>(names: [&str; N]) -> Self {
         Self {
             names: names.into_iter().map(ToOwned::to_owned).collect(),
             packager: Packager::guess(),
+            pin: Pin::default(),
         }
     }
 }
@@ -62,6 +72,7 @@ impl From<String> for Pkgs {
         Pkgs {
             names: vec![value],
             packager: Packager::guess(),
+            pin: Pin::default(),
         }
     }
 }
@@ -0,0 +1,49 @@
+use crate::deriv::packager::{PackagerType, Upgrade};
+use crate::prelude::*;
+
+/// Which pending upgrades get applied. pacman only upgrades its repositories
+/// as a whole so with paru anything but [`UpgradePolicy::All`] fails unless
+/// the repository packages it leaves out are held.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpgradePolicy {
+    /// Everything that has a newer version.
+    #[default]
+    All,
+    /// Only packages declared in the config. Held packages and upgrades that
+    /// leave a package's pinned version are skipped.
+    Declared,
+    /// Everything apart from held packages and packages pinned to a version.
+    ExcludeHeld,
+}
+
+/// The upgrades that are about to be applied, grouped by packager.
+#[derive(Debug, Default)]
+pub struct UpgradePlan {
+    steps: Vec<(Packager, Vec<Upgrade>)>,
+}
+
+impl UpgradePlan {
+    pub(crate) fn push(&mut self, pkgr: Packager, upgrades: Vec<Upgrade>) {
+        if !upgrades.is_empty() {
+            self.steps.push((pkgr, upgrades));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Every upgrade along with the packager that does it.
+    pub fn upgrades(&self) -> impl Iterator<Item = (&PackagerType, &Upgrade)> {
+        self.steps
+            .iter()
+            .flat_map(|(pkgr, upgrades)| upgrades.iter().map(|u| (pkgr.packager_type(), u)))
+    }
+
+    pub fn apply(self) -> Result<()> {
+        for (pkgr, upgrades) in self.steps {
+            pkgr.upgrade(upgrades.into_iter().map(|u| u.name).collect())?;
+        }
+        Ok(())
+    }
+}
@@ -70,11 +70,11 @@ impl Shipyard {
                         let f = fs::File::open(file.path())?;
                         let data: ShipmentData = json::from_reader(f)?;
                         if let Some(sp) = self.shipments.iter_mut().find(|sp| sp.name == name) {
-                            sp.packagers.push((ptype, data))
+                            sp.packagers.push((ptype.clone(), data))
                         } else {
                             self.shipments.push(Shipment {
                                 name,
-                                packagers: vec![(ptype, data)],
+                                packagers: vec![(ptype.clone(), data)],
                             })
                         }
                     }
@@ -1,3 +1,5 @@
+use crate::cmd;
+
 use std::process::Command;
 
 use serde::{Deserialize, Serialize};
@@ -45,15 +47,14 @@ struct OpenRcServicer;
 impl ServiceBackend for OpenRcServicer {
     fn enable(&mut self, names: &[&str]) {
         for name in names {
-            Command::new("sudo")
-                .arg("rc-update")
-                .arg("add")
-                .arg(name)
-                .arg("default")
-                .spawn()
-                .unwrap()
-                .wait()
-                .unwrap();
+            cmd::status(
+                Command::new("sudo")
+                    .arg("rc-update")
+                    .arg("add")
+                    .arg(name)
+                    .arg("default"),
+            )
+            .unwrap();
         }
     }
 
@@ -75,20 +76,13 @@ impl ServiceBackend for OpenRcServicer {
     }
 
     fn list_leaves_enabled(&mut self) -> Vec<String> {
-        String::from_utf8(
-            Command::new("sudo")
-                .arg("rc-status")
-                .arg("default")
-                .output()
-                .unwrap()
-                .stdout,
-        )
-        .unwrap()
-        .lines()
-        .skip(1)
-        .map(|s| s.split('[').next().unwrap())
-        .map(|s| s.trim())
-        .map(|s| s.to_string())
-        .collect()
+        cmd::output(Command::new("sudo").arg("rc-status").arg("default"))
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|s| s.split('[').next().unwrap())
+            .map(|s| s.trim())
+            .map(|s| s.to_string())
+            .collect()
     }
 }
@@ -2,6 +2,7 @@ pub use resu::eyre::Result;
 
 // pub type YumaResult = std::result::Result<(), YumaError>;
 use std::io;
+use std::process::{Command, ExitStatus};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,8 +13,40 @@ pub enum YumaError {
     Log(#[from] log::SetLoggerError),
     #[error("Packages specified that could not be resolved: {name:?}")]
     InvalidPackage { name: String },
+    #[error("Checksum of {name} did not match, expected {expected} but found {found}")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        found: String,
+    },
+    #[error("`{program} {}` failed with {status}: {stderr}", args.join(" "))]
+    Command {
+        program: String,
+        args: Vec<String>,
+        status: ExitStatus,
+        /// What the program printed to stderr. This is empty when the command
+        /// was attached to the terminal.
+        stderr: String,
+    },
+    #[error("This packager can't {op}")]
+    Unsupported { op: &'static str },
     #[error(transparent)]
     Static(#[from] resu::eyre::Error),
     #[error("Unknown error")]
     Unknown,
 }
+
+impl YumaError {
+    /// Creates an error for a command that did not exit successfully.
+    pub fn command(cmd: &Command, status: ExitStatus, stderr: &[u8]) -> Self {
+        YumaError::Command {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            status,
+            stderr: String::from_utf8_lossy(stderr).trim().to_string(),
+        }
+    }
+}
@@ -13,12 +13,14 @@
 #![forbid(unsafe_code)]
 
 pub mod callbacks;
+pub mod cmd;
 pub mod ctx;
 pub mod deriv;
 pub mod error;
 pub mod log;
 mod macros;
 pub mod prelude;
+pub mod prompt;
 
 pub extern crate color_eyre as resu;
 
@@ -60,6 +60,42 @@ macro_rules! y {
     (@ PKGR fake) => {
         $crate::deriv::packager::PackagerType::Fake
     };
+    (@ PKGR apt) => {
+        $crate::deriv::packager::PackagerType::Apt
+    };
+    (@ PKGR dnf) => {
+        $crate::deriv::packager::PackagerType::Dnf
+    };
+    (@ PKGR apk) => {
+        $crate::deriv::packager::PackagerType::Apk
+    };
+    (@ PKGR xbps) => {
+        $crate::deriv::packager::PackagerType::Xbps
+    };
+    (@ PKGR cargo) => {
+        $crate::deriv::packager::PackagerType::Cargo
+    };
+    (@ PKGR pipx) => {
+        $crate::deriv::packager::PackagerType::Pipx
+    };
+    (@ PKGR npm) => {
+        $crate::deriv::packager::PackagerType::Npm
+    };
+    (@ PKGR go) => {
+        $crate::deriv::packager::PackagerType::Go
+    };
+    (@ PKGR nix) => {
+        $crate::deriv::packager::PackagerType::Nix
+    };
+    (@ PKGR rustup) => {
+        $crate::deriv::packager::PackagerType::Rustup
+    };
+    (@ PKGR flatpak) => {
+        $crate::deriv::packager::PackagerType::Flatpak {
+            scope: $crate::deriv::packager::FlatpakScope::User,
+            remote: ::std::string::String::from("flathub"),
+        }
+    };
     // (INSTALL $name:ident) => {};
 
     // line endings
@@ -0,0 +1,124 @@
+//! Every question yuma asks before changing the system goes through here so
+//! that a config can also run without anyone to answer, like from cron or a
+//! first boot script. See [`RunMode`].
+
+use crate::prelude::*;
+
+use std::{cell::Cell, collections::HashMap, path::Path};
+
+use color_eyre::eyre::{bail, WrapErr};
+use requestty::Question;
+use stub::Stub;
+
+/// How the questions yuma asks are answered. Each question has a name which
+/// is what [`RunMode::Answers`] is keyed by:
+///
+/// - `add_repos` before adding repositories,
+/// - `install` before installing packages,
+/// - `upgrade` before upgrading packages,
+/// - `remove` before pruning packages,
+/// - `remove_repos` before pruning repositories,
+/// - `remove_orphans` before removing orphaned dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RunMode {
+    /// Asks on the terminal. Without a terminal questions fail instead of
+    /// waiting for an answer that never comes.
+    #[default]
+    Interactive,
+    /// Answers yes to everything.
+    AssumeYes,
+    /// Answers no to everything so nothing is changed.
+    AssumeNo,
+    /// Answers by the name of the question. Anything missing is answered
+    /// with no.
+    Answers(HashMap<String, bool>),
+}
+
+impl RunMode {
+    /// Reads [`RunMode::Answers`] from a json file like
+    /// `{ "install": true, "remove": false }`.
+    pub fn answers_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read answers from {}", path.display()))?;
+        let answers = json::from_str(&contents)
+            .wrap_err_with(|| format!("Invalid answers in {}", path.display()))?;
+        Ok(Self::Answers(answers))
+    }
+
+    /// Whether questions are answered without a prompt.
+    pub fn is_unattended(&self) -> bool {
+        *self != RunMode::Interactive
+    }
+}
+
+impl Stub for RunMode {
+    fn stub() -> Self {
+        Self::default()
+    }
+}
+
+thread_local! {
+/// Whether the commands run right now have anyone to answer them. Only set
+/// while a ctx is working, see [`attend`].
+static UNATTENDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores whether commands were unattended before [`attend`] when dropped.
+pub(crate) struct Attendance {
+    previous: bool,
+}
+
+impl Drop for Attendance {
+    fn drop(&mut self) {
+        UNATTENDED.set(self.previous);
+    }
+}
+
+/// Runs the commands started until the returned guard is dropped the way
+/// `mode` answers questions, so the package managers don't ask when nobody
+/// is there to answer them.
+pub(crate) fn attend(mode: &RunMode) -> Attendance {
+    Attendance {
+        previous: UNATTENDED.replace(mode.is_unattended()),
+    }
+}
+
+/// Whether nobody is there to answer the package managers either, so they
+/// have to be told not to ask.
+pub(crate) fn is_unattended() -> bool {
+    UNATTENDED.get()
+}
+
+/// Asks a yes or no question, defaulting to no.
+pub(crate) fn confirm(mode: &RunMode, name: &str, message: impl Into<String>) -> Result<bool> {
+    let message = message.into();
+    let answer = match mode {
+        RunMode::Interactive => None,
+        RunMode::AssumeYes => Some(true),
+        RunMode::AssumeNo => Some(false),
+        RunMode::Answers(answers) => Some(answers.get(name).copied().unwrap_or_else(|| {
+            log::warn!("No answer for {name}, assuming no");
+            false
+        })),
+    };
+    if let Some(yes) = answer {
+        log::info!("{message} {}", if yes { "yes" } else { "no" });
+        return Ok(yes);
+    }
+
+    // requestty would wait forever on a closed or redirected stdin
+    if !atty::is(atty::Stream::Stdin) {
+        bail!("No terminal to answer {message:?}, set a run mode to run without one");
+    }
+
+    let q = Question::confirm(name)
+        .message(message)
+        .default(false)
+        .build();
+
+    let requestty::Answer::Bool(yes) = requestty::prompt_one(q)? else {
+        unreachable!()
+    };
+    Ok(yes)
+}
@@ -1,4 +1,14 @@
+mod common;
+
+use std::sync::Arc;
+
+use color_eyre::eyre::bail;
+
+use yuma::deriv::packager::{GenericName, PackageBackend, SpecficName};
 use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+use common::InHouse;
 
 #[test]
 fn implicit_drop_callback() {
@@ -29,3 +39,59 @@ fn feature() {
         ctx.add(pkgs)
     }
 }
+
+#[test]
+fn declined_install_still_prunes() {
+    let pkgr = Packager::register("declined", Arc::new(InHouse::with(&["stale"]))).unwrap();
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::AssumeNo);
+    ctx.add("tool".b().with_packager(pkgr));
+    ctx.update().unwrap();
+
+    let cached = json::to_value(&ctx).unwrap();
+    assert_eq!(
+        cached["packages"]["backends"][0]["prunable"],
+        json::json!(["stale"])
+    );
+}
+
+/// Can't remove anything.
+#[derive(Debug)]
+struct Stuck(InHouse);
+
+impl PackageBackend for Stuck {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        self.0.list_installed()
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.0.list_leaves()
+    }
+
+    fn install(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.0.install(pkgs)
+    }
+
+    fn remove(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        bail!("stuck")
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        name.into()
+    }
+}
+
+#[test]
+fn failed_prune_doesnt_panic() {
+    let pkgr = Packager::register("stuck", Arc::new(Stuck(InHouse::with(&["stale"])))).unwrap();
+
+    {
+        let mut ctx = YumaCtx::new();
+        ctx.run_mode(RunMode::AssumeYes);
+        ctx.add("tool".b().with_packager(pkgr));
+        ctx.update().unwrap();
+    }
+    fs::remove_file(".yumacache.json").unwrap();
+}
@@ -12,12 +12,14 @@ fn pkg_list_y_macros() {
     let q1 = Pkgs {
         names: vec![String::from("text")],
         packager: Packager::guess(),
+        pin: Default::default(),
     };
     assert_eq!(p1, q1);
 
     let q2 = Pkgs {
         names: vec![String::from("name")],
         packager: Packager::fake(),
+        pin: Default::default(),
     };
     assert_eq!(p2, q2);
 
@@ -28,6 +30,7 @@ fn pkg_list_y_macros() {
             String::from("c"),
         ])),
         packager: Packager::fake(),
+        pin: Default::default(),
     };
 
     assert_eq!(p3, q3);
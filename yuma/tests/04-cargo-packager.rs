@@ -0,0 +1,60 @@
+mod common;
+
+use yuma::deriv::packager::{CargoPackager, PackageBackend};
+use yuma::prelude::*;
+
+use common::scratch;
+
+const CRATES2: &str = r#"{
+  "installs": {
+    "ripgrep 14.1.0 (registry+https://github.com/rust-lang/crates.io-index)": {
+      "version_req": null,
+      "bins": ["rg"],
+      "features": [],
+      "all_features": false,
+      "no_default_features": false,
+      "profile": "release",
+      "target": "x86_64-unknown-linux-gnu",
+      "rustc": "rustc 1.75.0"
+    },
+    "just 1.23.0 (git+https://github.com/casey/just#1b5f2e1a)": {
+      "version_req": null,
+      "bins": ["just"],
+      "features": [],
+      "all_features": false,
+      "no_default_features": false,
+      "profile": "release",
+      "target": "x86_64-unknown-linux-gnu",
+      "rustc": "rustc 1.75.0"
+    }
+  }
+}"#;
+
+#[test]
+fn lists_crates_from_manifest() {
+    let home = scratch("cargo");
+    fs::write(home.join(".crates2.json"), CRATES2).unwrap();
+
+    let cargo = CargoPackager::with_home(&home);
+    let mut installed = cargo.list_installed().unwrap();
+    installed.sort();
+    assert_eq!(installed, ["just", "ripgrep"]);
+    assert_eq!(cargo.list_leaves().unwrap().len(), 2);
+
+    fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn empty_cargo_home() {
+    let cargo = CargoPackager::with_home("/nonexistent/yuma/cargo");
+    assert!(cargo.list_installed().unwrap().is_empty());
+}
+
+#[test]
+fn from_cargo_macro() {
+    y! {
+        PKG ripgrep FROM cargo AS p;
+    };
+
+    assert_eq!(p.packager, Packager::cargo());
+}
@@ -0,0 +1,29 @@
+use yuma::deriv::packager::{FlatpakScope, PackagerType};
+use yuma::prelude::*;
+
+#[test]
+fn scopes_are_separate_packagers() {
+    let user = Packager::flatpak(FlatpakScope::User, "flathub");
+    let system = Packager::flatpak(FlatpakScope::System, "flathub");
+    let beta = Packager::flatpak(FlatpakScope::User, "flathub-beta");
+
+    assert_ne!(user, system);
+    assert_ne!(user, beta);
+    assert_eq!(user, Packager::flatpak(FlatpakScope::User, "flathub"));
+}
+
+#[test]
+fn default_flatpak_packager() {
+    y! {
+        PKG firefox FROM flatpak AS p;
+    };
+
+    assert_eq!(p.packager, Packager::flatpak(FlatpakScope::User, "flathub"));
+    assert_eq!(
+        PackagerType::try_from("flatpak").unwrap(),
+        PackagerType::Flatpak {
+            scope: FlatpakScope::User,
+            remote: "flathub".into(),
+        }
+    );
+}
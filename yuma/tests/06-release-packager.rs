@@ -0,0 +1,121 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+use yuma::deriv::packager::{PackageBackend, Release, ReleasePackager};
+use yuma::prelude::*;
+
+use common::scratch;
+
+/// Packs `tool-1.0/bin/tool` into a tarball and returns its path and checksum.
+fn archive(dir: &Path) -> (PathBuf, String) {
+    let src = dir.join("src");
+    fs::create_dir_all(src.join("tool-1.0/bin")).unwrap();
+    fs::write(src.join("tool-1.0/bin/tool"), "#!/bin/sh\necho hi\n").unwrap();
+
+    let tarball = dir.join("tool-1.0.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(&src)
+        .arg("tool-1.0")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let sha: String = Sha256::digest(fs::read(&tarball).unwrap())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    (tarball, sha)
+}
+
+#[test]
+fn install_and_remove_release() {
+    let dir = scratch("release");
+    let (tarball, sha) = archive(&dir);
+    let bin_dir = dir.join("bin");
+
+    let pkgs = Release::new("tool", format!("file://{}", tarball.display()), sha)
+        .bin("tool-1.0/bin/tool")
+        .bin_dir(&bin_dir)
+        .declare();
+    assert_eq!(pkgs.packager, Packager::release(&bin_dir));
+
+    let pkgr = ReleasePackager::new(&bin_dir);
+    assert!(pkgr.list_installed().unwrap().is_empty());
+
+    pkgr.install(vec!["tool".into()]).unwrap();
+    assert!(bin_dir.join("tool").is_file());
+    assert_eq!(pkgr.list_installed().unwrap(), ["tool"]);
+    assert_eq!(pkgr.list_leaves().unwrap(), ["tool"]);
+
+    // its own files can be replaced
+    pkgr.install(vec!["tool".into()]).unwrap();
+
+    pkgr.remove(vec!["tool".into()]).unwrap();
+    assert!(!bin_dir.join("tool").exists());
+    assert!(pkgr.list_leaves().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn checksum_mismatch_installs_nothing() {
+    let dir = scratch("release-bad-sha");
+    let (tarball, _) = archive(&dir);
+    let bin_dir = dir.join("bin");
+
+    Release::new(
+        "tool",
+        format!("file://{}", tarball.display()),
+        "00".repeat(32),
+    )
+    .bin("tool-1.0/bin/tool")
+    .bin_dir(&bin_dir)
+    .declare();
+
+    let pkgr = ReleasePackager::new(&bin_dir);
+    assert!(pkgr.install(vec!["tool".into()]).is_err());
+    assert!(!bin_dir.join("tool").exists());
+    assert!(pkgr.list_leaves().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn foreign_and_partial_installs() {
+    let dir = scratch("release-foreign");
+    let (tarball, sha) = archive(&dir);
+    let url = format!("file://{}", tarball.display());
+
+    let bin_dir = dir.join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    fs::write(bin_dir.join("tool"), "mine").unwrap();
+    Release::new("tool", &url, &sha)
+        .bin("tool-1.0/bin/tool")
+        .bin_dir(&bin_dir)
+        .declare();
+
+    let pkgr = ReleasePackager::new(&bin_dir);
+    assert!(pkgr.install(vec!["tool".into()]).is_err());
+    assert_eq!(fs::read_to_string(bin_dir.join("tool")).unwrap(), "mine");
+    assert!(pkgr.list_leaves().unwrap().is_empty());
+
+    // the second binary isn't in the archive so the first isn't kept either
+    let other = dir.join("other");
+    Release::new("tools", &url, &sha)
+        .bin("tool-1.0/bin/tool")
+        .bin("tool-1.0/bin/missing")
+        .bin_dir(&other)
+        .declare();
+
+    let pkgr = ReleasePackager::new(&other);
+    assert!(pkgr.install(vec!["tools".into()]).is_err());
+    assert_eq!(fs::read_dir(&other).unwrap().count(), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
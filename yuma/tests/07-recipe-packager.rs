@@ -0,0 +1,175 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+use yuma::deriv::packager::{PackageBackend, Recipe, RecipePackager, Source};
+use yuma::prelude::*;
+
+use common::scratch;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=yuma", "-c", "user.email=yuma@localhost"])
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn rev_parse(dir: &Path, rev: &str) -> String {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", rev])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+fn hello(src: Source, prefix: &Path) -> Recipe {
+    Recipe::new("hello", src)
+        .build("cp hello.sh hello")
+        .install("mkdir -p \"$PREFIX/bin\" && cp hello \"$PREFIX/bin/hello\"")
+        .file("bin/hello")
+        .prefix(prefix)
+}
+
+#[test]
+fn rebuilds_when_recipe_changes() {
+    let dir = scratch("recipe-path");
+    let src = dir.join("src");
+    let prefix = dir.join("prefix");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("hello.sh"), "echo hello\n").unwrap();
+
+    let pkgs = hello(Source::Path(src.clone()), &prefix).declare();
+    assert_eq!(pkgs.packager, Packager::recipe(&prefix));
+
+    let pkgr = RecipePackager::new(&prefix);
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert!(prefix.join("bin/hello").is_file());
+    assert_eq!(pkgr.list_installed().unwrap(), ["hello"]);
+
+    // an edited recipe is out of date
+    hello(Source::Path(src), &prefix)
+        .install("mkdir -p \"$PREFIX/bin\" && cp hello \"$PREFIX/bin/hello\" && true")
+        .declare();
+    assert!(pkgr.list_installed().unwrap().is_empty());
+    assert_eq!(pkgr.list_leaves().unwrap(), ["hello"]);
+
+    pkgr.remove(vec!["hello".into()]).unwrap();
+    assert!(!prefix.join("bin/hello").exists());
+    assert!(pkgr.list_leaves().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rebuilds_when_source_changes() {
+    let dir = scratch("recipe-git");
+    let upstream = dir.join("upstream");
+    let prefix = dir.join("prefix");
+    fs::create_dir_all(&upstream).unwrap();
+    fs::write(upstream.join("hello.sh"), "echo hello\n").unwrap();
+    git(&upstream, &["init", "--quiet"]);
+    git(&upstream, &["add", "."]);
+    git(&upstream, &["commit", "--quiet", "-m", "init"]);
+
+    let src = Source::Git {
+        url: upstream.display().to_string(),
+        rev: None,
+    };
+    hello(src, &prefix).declare();
+
+    let pkgr = RecipePackager::new(&prefix);
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert_eq!(pkgr.list_installed().unwrap(), ["hello"]);
+    let checkout = prefix.join("share/yuma/src/hello");
+    let fetched = rev_parse(&checkout, "origin/HEAD");
+
+    fs::write(upstream.join("hello.sh"), "echo hello world\n").unwrap();
+    git(&upstream, &["commit", "--quiet", "-am", "louder"]);
+    assert!(pkgr.list_installed().unwrap().is_empty());
+
+    // asking didn't fetch anything into the checkout
+    assert_eq!(rev_parse(&checkout, "origin/HEAD"), fetched);
+
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/hello")).unwrap(),
+        "echo hello world\n"
+    );
+    assert_eq!(pkgr.list_installed().unwrap(), ["hello"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn follows_branch_rev() {
+    let dir = scratch("recipe-branch");
+    let upstream = dir.join("upstream");
+    let prefix = dir.join("prefix");
+    fs::create_dir_all(&upstream).unwrap();
+    fs::write(upstream.join("hello.sh"), "echo hello\n").unwrap();
+    git(&upstream, &["init", "--quiet"]);
+    git(&upstream, &["add", "."]);
+    git(&upstream, &["commit", "--quiet", "-m", "init"]);
+    git(&upstream, &["checkout", "--quiet", "-b", "dev"]);
+    fs::write(upstream.join("hello.sh"), "echo dev\n").unwrap();
+    git(&upstream, &["commit", "--quiet", "-am", "dev"]);
+    // the default branch is not the one followed
+    git(&upstream, &["checkout", "--quiet", "-"]);
+
+    let src = Source::Git {
+        url: upstream.display().to_string(),
+        rev: Some("dev".into()),
+    };
+    hello(src, &prefix).declare();
+
+    let pkgr = RecipePackager::new(&prefix);
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/hello")).unwrap(),
+        "echo dev\n"
+    );
+    assert_eq!(pkgr.list_installed().unwrap(), ["hello"]);
+
+    git(&upstream, &["checkout", "--quiet", "dev"]);
+    fs::write(upstream.join("hello.sh"), "echo dev again\n").unwrap();
+    git(&upstream, &["commit", "--quiet", "-am", "again"]);
+    assert!(pkgr.list_installed().unwrap().is_empty());
+
+    // the rebuild uses the new commit and is current afterwards
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/hello")).unwrap(),
+        "echo dev again\n"
+    );
+    assert_eq!(pkgr.list_installed().unwrap(), ["hello"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn files_stay_in_prefix() {
+    let dir = scratch("recipe-escape");
+    let prefix = dir.join("prefix");
+
+    for file in ["/etc/passwd", "../outside", "bin/../../outside"] {
+        Recipe::new("escape", Source::Path(dir.clone()))
+            .file(file)
+            .prefix(&prefix)
+            .declare();
+
+        let err = RecipePackager::new(&prefix)
+            .install(vec!["escape".into()])
+            .unwrap_err();
+        assert!(err.to_string().contains("stay inside the prefix"));
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use yuma::deriv::packager::freight::{FreightArchive, InstallDb, Manifest};
+use yuma::deriv::packager::{FreightPackager, PackageBackend};
+use yuma::prelude::*;
+
+use common::scratch;
+
+/// Writes the given files under `root` and packs them into the repo.
+fn package(root: &Path, repo: &Path, name: &str, version: &str, files: &[&str], deps: &[&str]) {
+    let _ = fs::remove_dir_all(root);
+    for file in files {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("{name} {version}\n")).unwrap();
+    }
+
+    let manifest = Manifest {
+        name: name.into(),
+        version: version.into(),
+        files: files.iter().map(PathBuf::from).collect(),
+        dependencies: deps.iter().map(ToString::to_string).collect(),
+    };
+    FreightArchive::pack(manifest, root, repo).unwrap();
+}
+
+#[test]
+fn install_upgrade_and_remove() {
+    let dir = scratch("freight");
+    let (root, repo, prefix) = (dir.join("root"), dir.join("repo"), dir.join("prefix"));
+
+    package(&root, &repo, "libhello", "1.0", &["lib/libhello.so"], &[]);
+    package(
+        &root,
+        &repo,
+        "hello",
+        "1.0",
+        &["bin/hello", "share/hello/old"],
+        &["libhello"],
+    );
+
+    let pkgr = FreightPackager::new(&prefix, &repo);
+    pkgr.install(vec!["hello".into()]).unwrap();
+
+    // dependencies come along but are not leaves
+    assert!(prefix.join("lib/libhello.so").is_file());
+    assert!(prefix.join("share/hello/old").is_file());
+    let mut installed = pkgr.list_installed().unwrap();
+    installed.sort();
+    assert_eq!(installed, ["hello", "libhello"]);
+    assert_eq!(pkgr.list_leaves().unwrap(), ["hello"]);
+
+    // a newer archive makes the old one look missing
+    package(&root, &repo, "hello", "1.10", &["bin/hello"], &["libhello"]);
+    assert_eq!(pkgr.list_installed().unwrap(), ["libhello"]);
+
+    pkgr.install(vec!["hello".into()]).unwrap();
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/hello")).unwrap(),
+        "hello 1.10\n"
+    );
+    assert!(!prefix.join("share/hello/old").exists());
+
+    let db = InstallDb::load(&prefix).unwrap();
+    assert_eq!(db.packages["hello"].version, "1.10");
+
+    // still needed by hello
+    assert!(pkgr.remove(vec!["libhello".into()]).is_err());
+
+    pkgr.remove(vec!["hello".into(), "libhello".into()])
+        .unwrap();
+    assert!(!prefix.join("bin/hello").exists());
+    assert!(!prefix.join("lib/libhello.so").exists());
+    assert!(pkgr.list_installed().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn conflicting_files_are_refused() {
+    let dir = scratch("freight-conflict");
+    let (root, repo, prefix) = (dir.join("root"), dir.join("repo"), dir.join("prefix"));
+
+    package(&root, &repo, "one", "1", &["bin/tool"], &[]);
+    package(&root, &repo, "two", "1", &["bin/tool"], &[]);
+
+    let pkgr = FreightPackager::new(&prefix, &repo);
+    pkgr.install(vec!["one".into()]).unwrap();
+    assert!(pkgr.install(vec!["two".into()]).is_err());
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/tool")).unwrap(),
+        "one 1\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn foreign_files_are_refused() {
+    let dir = scratch("freight-foreign");
+    let (root, repo, prefix) = (dir.join("root"), dir.join("repo"), dir.join("prefix"));
+
+    package(&root, &repo, "rg", "1", &["bin/a", "bin/rg"], &[]);
+    fs::create_dir_all(prefix.join("bin")).unwrap();
+    fs::write(prefix.join("bin/rg"), "by hand\n").unwrap();
+
+    let pkgr = FreightPackager::new(&prefix, &repo);
+    let err = pkgr.install(vec!["rg".into()]).unwrap_err();
+    assert!(err.to_string().contains("belongs to no package"), "{err}");
+    assert_eq!(
+        fs::read_to_string(prefix.join("bin/rg")).unwrap(),
+        "by hand\n"
+    );
+    assert!(!prefix.join("bin/a").exists());
+    assert!(pkgr.list_installed().unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn partial_unpacks_are_rolled_back() {
+    let dir = scratch("freight-partial");
+    let (root, repo, prefix) = (dir.join("root"), dir.join("repo"), dir.join("prefix"));
+
+    package(&root, &repo, "two", "1", &["bin/one", "bin/two"], &[]);
+    // the second file can't be copied into a directory in its way
+    fs::create_dir_all(prefix.join("bin/.two.freight-new")).unwrap();
+
+    let archive = FreightArchive::open(repo.join("two-1.freight")).unwrap();
+    assert!(archive.unpack(&prefix).is_err());
+    let left: Vec<_> = fs::read_dir(prefix.join("bin"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(left, [".two.freight-new"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hostile_manifests_are_refused() {
+    let dir = scratch("freight-hostile");
+    let (work, repo, prefix) = (dir.join("work"), dir.join("repo"), dir.join("prefix"));
+
+    // packed by hand since `pack` won't write such a manifest
+    fs::create_dir_all(work.join("files")).unwrap();
+    fs::write(work.join("files/escape"), "gotcha\n").unwrap();
+    let manifest = Manifest {
+        name: "evil".into(),
+        version: "1".into(),
+        files: vec!["../escape".into()],
+        dependencies: vec![],
+    };
+    fs::write(
+        work.join("manifest.json"),
+        json::to_string(&manifest).unwrap(),
+    )
+    .unwrap();
+    fs::create_dir_all(&repo).unwrap();
+    let archive = repo.join("evil-1.freight");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&work)
+        .args(["manifest.json", "files"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert!(FreightArchive::open(&archive).is_err());
+    let pkgr = FreightPackager::new(&prefix, &repo);
+    assert!(pkgr.install(vec!["evil".into()]).is_err());
+    assert!(!dir.join("escape").exists());
+
+    let absolute = FreightArchive {
+        path: archive,
+        manifest: Manifest {
+            files: vec!["/etc/profile".into()],
+            ..manifest
+        },
+    };
+    assert!(absolute.unpack(&prefix).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
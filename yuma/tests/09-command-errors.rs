@@ -0,0 +1,44 @@
+mod common;
+
+use std::process::Command;
+
+use yuma::deriv::packager::{CargoPackager, PackageBackend};
+use yuma::prelude::*;
+
+use common::scratch;
+
+#[test]
+fn command_error_carries_details() {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "echo boom >&2; exit 3"]);
+    let out = cmd.output().unwrap();
+
+    let err = YumaError::command(&cmd, out.status, &out.stderr);
+    let YumaError::Command {
+        program,
+        args,
+        status,
+        stderr,
+    } = &err
+    else {
+        panic!("wrong variant: {err:?}");
+    };
+
+    assert_eq!(program, "sh");
+    assert_eq!(args, &["-c", "echo boom >&2; exit 3"]);
+    assert_eq!(status.code(), Some(3));
+    assert_eq!(stderr, "boom");
+    assert!(err.to_string().contains("boom"));
+}
+
+#[test]
+fn broken_state_is_an_error() {
+    let home = scratch("cargo-broken");
+    fs::write(home.join(".crates2.json"), "not json").unwrap();
+
+    let cargo = CargoPackager::with_home(&home);
+    assert!(cargo.list_installed().is_err());
+    assert!(cargo.list_leaves().is_err());
+
+    fs::remove_dir_all(&home).unwrap();
+}
@@ -0,0 +1,113 @@
+use std::process::Command;
+use std::sync::Arc;
+
+use yuma::cmd::{self, CommandRunner, ScriptedRunner, SystemRunner};
+use yuma::deriv::packager::GenericName;
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+fn scripted(runner: ScriptedRunner) -> Arc<ScriptedRunner> {
+    let runner = Arc::new(runner);
+    cmd::set_runner(runner.clone());
+    runner
+}
+
+#[test]
+fn paru_invocations() {
+    let runner = scripted(
+        ScriptedRunner::new()
+            .reply("paru -Qq", "base\nlinux\nneovim\n")
+            .reply("paru -Qqt", "neovim\n"),
+    );
+    let paru = Packager::paru();
+
+    assert_eq!(paru.list_installed().unwrap(), ["base", "linux", "neovim"]);
+    assert_eq!(paru.list_leaves().unwrap(), ["neovim"]);
+    paru.install(vec!["ripgrep".into(), "fd".into()]).unwrap();
+    paru.remove(vec!["neovim".into()]).unwrap();
+
+    assert_eq!(
+        runner.lines(),
+        [
+            "paru -Qq",
+            "paru -Qqt",
+            "paru -S --needed ripgrep fd",
+            "paru -Rns neovim",
+        ]
+    );
+}
+
+#[test]
+fn install_flow() {
+    let runner = scripted(
+        ScriptedRunner::new()
+            .reply("paru -Qq", "base\nneovim\nstale\n")
+            .reply("paru -Qqt", "neovim\nstale\n"),
+    );
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::AssumeYes);
+    ctx.add(["ripgrep", "neovim"].b().with_packager(Packager::paru()));
+    ctx.update().unwrap();
+
+    // only what is missing gets installed
+    assert_eq!(
+        runner.lines(),
+        [
+            "paru -Qqt",
+            "paru -Qq",
+            "paru -S --noconfirm --needed ripgrep",
+        ]
+    );
+    // the leaves from before the install, declared ones are skipped on prune
+    let cached = json::to_value(&ctx).unwrap();
+    let mut prunable: Vec<String> =
+        json::from_value(cached["packages"]["backends"][0]["prunable"].clone()).unwrap();
+    prunable.sort();
+    assert_eq!(prunable, ["neovim", "stale"]);
+}
+
+#[test]
+fn brew_invocations() {
+    let runner = scripted(ScriptedRunner::new().reply("brew leaves", "git\n"));
+    let brew = Packager::brew();
+
+    assert_eq!(brew.list_leaves().unwrap(), ["git"]);
+    brew.install(vec!["jq".into()]).unwrap();
+
+    let calls = runner.calls();
+    assert_eq!(calls[1].program, "brew");
+    assert_eq!(calls[1].args, ["install", "jq"]);
+
+    let name = brew.resolve_name(GenericName::new("jq".into()));
+    assert_eq!(name, "jq");
+}
+
+#[test]
+fn scripted_failures_are_errors() {
+    let runner =
+        scripted(ScriptedRunner::new().fail("paru -S --needed nope", 1, "target not found"));
+
+    let err = Packager::paru().install(vec!["nope".into()]).unwrap_err();
+    let Some(YumaError::Command {
+        program, status, ..
+    }) = err.downcast_ref()
+    else {
+        panic!("wrong error: {err:?}");
+    };
+    assert_eq!(program, "paru");
+    assert_eq!(status.code(), Some(1));
+    assert_eq!(runner.lines(), ["paru -S --needed nope"]);
+}
+
+#[test]
+fn large_stdin_is_echoed() {
+    // more than fits in a pipe, cat blocks on stdout until it is read
+    let input = "yuma\n".repeat(64 * 1024);
+    let out = SystemRunner
+        .output_with_stdin(&mut Command::new("cat"), input.as_bytes())
+        .unwrap();
+    assert!(out.status.success());
+    assert_eq!(out.stdout, input.as_bytes());
+}
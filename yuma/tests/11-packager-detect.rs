@@ -0,0 +1,118 @@
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use yuma::deriv::packager::detect::Detector;
+use yuma::deriv::packager::PackagerType;
+use yuma::prelude::*;
+
+use common::scratch;
+
+/// A fake system root with an os-release file and some programs in /usr/bin.
+fn root(name: &str, os_release: Option<&str>, programs: &[&str]) -> PathBuf {
+    let root = scratch(&format!("detect-{name}"));
+    fs::create_dir_all(root.join("etc")).unwrap();
+    fs::create_dir_all(root.join("usr/bin")).unwrap();
+
+    if let Some(os_release) = os_release {
+        fs::write(root.join("etc/os-release"), os_release).unwrap();
+    }
+    for program in programs {
+        let path = root.join("usr/bin").join(program);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    root
+}
+
+fn detector(root: &PathBuf) -> Detector {
+    Detector::new()
+        .root(root)
+        .path("/usr/bin")
+        .requested(None)
+        .preferred(None)
+}
+
+#[test]
+fn detects_from_os_release() {
+    let debian = root(
+        "debian",
+        Some("PRETTY_NAME=\"Debian GNU/Linux 12\"\nID=debian\n"),
+        &["apt-get", "brew"],
+    );
+    assert_eq!(detector(&debian).detect().unwrap(), PackagerType::Apt);
+
+    // ID_LIKE is used when the ID itself is unknown
+    let mint = root(
+        "mint",
+        Some("ID=\"someremix\"\nID_LIKE=\"ubuntu debian\"\n"),
+        &["brew", "apt-get"],
+    );
+    assert_eq!(detector(&mint).detect().unwrap(), PackagerType::Apt);
+
+    fs::remove_dir_all(debian).unwrap();
+    fs::remove_dir_all(mint).unwrap();
+}
+
+#[test]
+fn falls_back_to_path() {
+    // arch without paru installed should not pick paru
+    let arch = root("arch", Some("ID=arch\n"), &["brew"]);
+    assert_eq!(detector(&arch).detect().unwrap(), PackagerType::Brew);
+
+    let mac = root("mac", None, &["brew"]);
+    assert_eq!(detector(&mac).detect().unwrap(), PackagerType::Brew);
+
+    fs::remove_dir_all(arch).unwrap();
+    fs::remove_dir_all(mac).unwrap();
+}
+
+#[test]
+fn overrides() {
+    let debian = root("override", Some("ID=debian\n"), &["apt-get"]);
+
+    let preferred = detector(&debian).preferred(Some(PackagerType::Nix));
+    assert_eq!(preferred.detect().unwrap(), PackagerType::Nix);
+
+    // the environment beats everything else
+    let requested = preferred.requested(Some("paru"));
+    assert_eq!(requested.detect().unwrap(), PackagerType::Paru);
+    assert!(detector(&debian).requested(Some("nope")).detect().is_err());
+
+    fs::remove_dir_all(debian).unwrap();
+}
+
+#[test]
+fn nothing_matches() {
+    let empty = root("empty", Some("ID=haiku\n"), &[]);
+    let err = detector(&empty).detect().unwrap_err();
+    assert!(err.to_string().contains("YUMA_PACKAGER"));
+
+    fs::remove_dir_all(empty).unwrap();
+}
+
+#[test]
+fn default_packager_stays_with_its_ctx() {
+    let mut brewed = YumaCtx::stub();
+    brewed.default_packager(Packager::brew());
+    brewed.add("git");
+    brewed.add("make".b().with_packager(Packager::fake()));
+
+    let mut plain = YumaCtx::stub();
+    plain.add("git");
+
+    let cached = json::to_value(&brewed).unwrap();
+    assert_eq!(
+        cached["packages"]["backends"][0]["pkgr"],
+        json::to_value(Packager::brew()).unwrap()
+    );
+    assert_eq!(
+        cached["packages"]["backends"][1]["pkgr"],
+        json::to_value(Packager::fake()).unwrap()
+    );
+    assert_eq!(
+        json::to_value(&plain).unwrap()["packages"]["backends"][0]["pkgr"],
+        json::to_value(Packager::guess()).unwrap()
+    );
+}
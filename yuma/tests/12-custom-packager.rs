@@ -0,0 +1,54 @@
+mod common;
+
+use std::sync::Arc;
+
+use yuma::deriv::packager::{PackageBackend, PackagerType};
+use yuma::deriv::pkg::list::AsPkgList;
+use yuma::prelude::*;
+
+use common::InHouse;
+
+#[test]
+fn registered_by_name() {
+    let backend = Arc::new(InHouse::default());
+    let inhouse = Packager::register("inhouse", backend.clone()).unwrap();
+
+    let ptype = PackagerType::try_from("inhouse").unwrap();
+    assert_eq!(ptype, PackagerType::Custom("inhouse".into()));
+
+    let mut pkgs = Pkgs::from("tool");
+    pkgs.backend(ptype);
+    assert_eq!(pkgs.packager, inhouse);
+
+    pkgs.packager.install(pkgs.names.clone()).unwrap();
+    assert_eq!(backend.list_installed().unwrap(), ["tool"]);
+}
+
+#[test]
+fn builder_and_cache() {
+    let backend = Arc::new(InHouse::default());
+    Packager::register("tooling", backend.clone()).unwrap();
+
+    let [pkgs] = <[Pkgs; 1]>::try_from(
+        "linter"
+            .b()
+            .with_packager(Packager::custom("tooling"))
+            .list(),
+    )
+    .unwrap();
+
+    // a round trip through the cache still reaches the registered backend
+    let cached = json::to_string(&pkgs).unwrap();
+    let pkgs: Pkgs = json::from_str(&cached).unwrap();
+    assert_eq!(pkgs.packager, Packager::custom("tooling"));
+
+    pkgs.packager.install(pkgs.names).unwrap();
+    assert_eq!(backend.list_installed().unwrap(), ["linter"]);
+}
+
+#[test]
+fn unknown_and_builtin_names() {
+    assert!(PackagerType::try_from("nobody").is_err());
+    assert!(Packager::custom("nobody").list_installed().is_err());
+    assert!(Packager::register("brew", Arc::new(InHouse::default())).is_err());
+}
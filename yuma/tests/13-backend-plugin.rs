@@ -0,0 +1,63 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::detect::Detector;
+use yuma::deriv::packager::{GenericName, PackagerType, PluginPackager};
+use yuma::prelude::*;
+
+use common::scratch;
+
+fn plugins() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/plugins")
+}
+
+#[test]
+fn reference_plugin() {
+    assert!(Detector::new()
+        .path(plugins())
+        .has_program("freight-backend-shelf"));
+    assert!(PackagerType::try_from("no-such-plugin").is_err());
+
+    let shelf = scratch("shelf-reference").join("shelf");
+    let backend = PluginPackager::new("shelf")
+        .dir(plugins())
+        .env("SHELF", &shelf);
+    let shelf_pkgr = Packager::register("shelf-reference", Arc::new(backend)).unwrap();
+
+    assert!(shelf_pkgr.list_installed().unwrap().is_empty());
+    shelf_pkgr
+        .install(vec!["ripgrep".into(), "fd".into()])
+        .unwrap();
+    assert_eq!(shelf_pkgr.list_installed().unwrap(), ["ripgrep", "fd"]);
+
+    shelf_pkgr.remove(vec!["ripgrep".into()]).unwrap();
+    assert_eq!(shelf_pkgr.list_leaves().unwrap(), ["fd"]);
+
+    let name = shelf_pkgr.resolve_name(GenericName::new("fd".into()));
+    assert_eq!(name, "shelf-fd");
+
+    let err = shelf_pkgr.install(vec!["broken".into()]).unwrap_err();
+    assert!(err.to_string().contains("broken is broken"));
+
+    fs::remove_file(shelf).unwrap();
+}
+
+#[test]
+fn request_format() {
+    let runner = Arc::new(ScriptedRunner::new().reply("freight-backend-fmt", r#"{"ok":null}"#));
+    cmd::set_runner(runner.clone());
+
+    Packager::plugin("fmt")
+        .install(vec!["a".into(), "b".into()])
+        .unwrap();
+
+    let calls = runner.calls();
+    assert_eq!(calls[0].program, "freight-backend-fmt");
+    assert_eq!(
+        calls[0].stdin.as_deref(),
+        Some(r#"{"op":"install","pkgs":["a","b"]}"#)
+    );
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{InstallReason, PackageInfo};
+use yuma::prelude::*;
+
+const PARU_QI: &str = "\
+Name            : linux
+Version         : 6.9.1.arch1-1
+Description     : The Linux kernel and modules
+Architecture    : x86_64
+Depends On      : coreutils  kmod
+Optional Deps   : wireless-regdb: to set the correct wireless channels
+                  linux-firmware: firmware images needed for some devices
+Installed Size  : 131.50 MiB
+Install Reason  : Explicitly installed
+
+Name            : paru-bin
+Version         : 2.0.3-1
+Description     : Feature packed AUR helper
+Installed Size  : 8.00 KiB
+Install Reason  : Explicitly installed
+
+Name            : zlib
+Version         : 1:1.3.1-1
+Description     : None
+Installed Size  : 363.00 KiB
+Install Reason  : Installed as a dependency for another package
+
+";
+
+#[test]
+fn paru_installed_info() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .reply("paru -Qi", PARU_QI)
+            .reply("paru -Qqm", "paru-bin\n")
+            .reply(
+                "pacman -Sl",
+                "core linux 6.9.1.arch1-1 [installed]\ncore zlib 1:1.3.1-1 [installed]\ncore vim 9.1-1\n",
+            ),
+    ));
+
+    let infos = Packager::paru().installed_info().unwrap();
+    assert_eq!(
+        infos[0],
+        PackageInfo {
+            name: "linux".into(),
+            version: "6.9.1.arch1-1".into(),
+            description: Some("The Linux kernel and modules".into()),
+            repository: Some("core".into()),
+            installed_size: Some(131 * 1024 * 1024 + 512 * 1024),
+            reason: Some(InstallReason::Explicit),
+        }
+    );
+    assert_eq!(infos[1].repository.as_deref(), Some("aur"));
+    assert_eq!(infos[2].description, None);
+    assert_eq!(infos[2].reason, Some(InstallReason::Dependency));
+}
+
+#[test]
+fn paru_single_info() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .fail("paru -Qi vim", 1, "error: package 'vim' was not found")
+            .reply(
+                "paru -Si vim",
+                "Repository      : extra\nName            : vim\nVersion         : 9.1-1\nDescription     : Vi Improved\nInstalled Size  : 4.00 MiB\n\n",
+            )
+            .fail("paru -Qi nope", 1, "error: package 'nope' was not found")
+            .fail("paru -Si nope", 1, "error: package 'nope' was not found"),
+    ));
+
+    let vim = Packager::paru().info("vim").unwrap().unwrap();
+    assert_eq!(vim.repository.as_deref(), Some("extra"));
+    assert!(!vim.is_installed());
+
+    assert_eq!(Packager::paru().info("nope").unwrap(), None);
+}
+
+#[test]
+fn brew_info() {
+    let installed = r#"{
+        "formulae": [{
+            "name": "jq",
+            "tap": "homebrew/core",
+            "desc": "Lightweight and flexible command-line JSON processor",
+            "versions": { "stable": "1.7.1" },
+            "installed": [{ "version": "1.7.1", "installed_on_request": true }]
+        }, {
+            "name": "oniguruma",
+            "tap": "homebrew/core",
+            "desc": "Regular expressions library",
+            "versions": { "stable": "6.9.9" },
+            "installed": [{ "version": "6.9.9", "installed_on_request": false }]
+        }],
+        "casks": [{
+            "token": "firefox",
+            "tap": "homebrew/cask",
+            "desc": "Web browser",
+            "version": "126.0",
+            "installed": "125.0"
+        }]
+    }"#;
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .reply("brew info --json=v2 --installed", installed)
+            .fail(
+                "brew info --json=v2 nope",
+                1,
+                "Error: No available formula with the name \"nope\".",
+            ),
+    ));
+
+    let infos = Packager::brew().installed_info().unwrap();
+    let names: Vec<_> = infos.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["jq", "oniguruma", "firefox"]);
+    assert!(infos[0].is_explicit());
+    assert_eq!(infos[1].reason, Some(InstallReason::Dependency));
+    assert_eq!(infos[2].version, "125.0");
+
+    assert_eq!(Packager::brew().info("nope").unwrap(), None);
+    assert!(Packager::fake().installed_info().is_err());
+}
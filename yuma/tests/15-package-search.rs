@@ -0,0 +1,117 @@
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::detect::Detector;
+use yuma::deriv::packager::{self, FakePackager, PackagerType};
+use yuma::prelude::*;
+
+use common::scratch;
+
+const PARU_SS: &str = "\
+extra/ripgrep 14.1.0-1 [1.23 MiB 4.50 MiB] [Installed]
+    A search tool that combines the usability of ag with the raw speed of grep
+aur/ripgrep-all 0.10.6-1 [+54 ~1.20]
+    rga: ripgrep, but also search in PDFs, E-Books, Office documents, zip, tar.gz, etc.
+extra/ugrep 6.1.0-1 [0.60 MiB 1.80 MiB]
+    Ultra fast grep with interactive query UI, faster than ripgrep
+";
+
+const BREW_SEARCH: &str = "\
+==> Formulae
+ripgrep: Search tool like grep and The Silver Searcher
+ripgrep-all: Wrapper around ripgrep that adds multiple rich file types
+";
+
+#[test]
+fn search_across_backends() {
+    // only paru and brew exist on this system
+    let bin = scratch("search");
+    for program in ["paru", "brew"] {
+        let path = bin.join(program);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply("paru -Ss ripgrep", PARU_SS)
+            .reply("brew search --desc --eval-all ripgrep", BREW_SEARCH),
+    );
+    cmd::set_runner(runner.clone());
+    // can't search but shouldn't get in the way
+    Packager::register("quiet", Arc::new(FakePackager)).unwrap();
+
+    let hits = packager::search_with(&Detector::new().path(&bin), "ripgrep");
+    let found: Vec<_> = hits.iter().map(|h| (&h.packager, h.name())).collect();
+    assert_eq!(
+        found,
+        [
+            (&PackagerType::Paru, "ripgrep"),
+            (&PackagerType::Brew, "ripgrep"),
+            (&PackagerType::Paru, "ripgrep-all"),
+            (&PackagerType::Brew, "ripgrep-all"),
+            (&PackagerType::Paru, "ugrep"),
+        ]
+    );
+    assert_eq!(
+        hits[0].description(),
+        Some("A search tool that combines the usability of ag with the raw speed of grep")
+    );
+    assert_eq!(hits[0].info.version, "14.1.0-1");
+    assert_eq!(hits[2].info.repository.as_deref(), Some("aur"));
+    assert_eq!(runner.calls().len(), 2);
+
+    fs::remove_dir_all(bin).unwrap();
+}
+
+#[test]
+fn apt_search() {
+    cmd::set_runner(Arc::new(ScriptedRunner::new().reply(
+        "apt-cache search fd-find",
+        "fd-find - Simple, fast and user-friendly alternative to find\n",
+    )));
+
+    let hits = Packager::apt().search("fd-find").unwrap();
+    assert_eq!(hits[0].name, "fd-find");
+    assert_eq!(
+        hits[0].description.as_deref(),
+        Some("Simple, fast and user-friendly alternative to find")
+    );
+}
+
+#[test]
+fn no_matches_or_failures() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .fail("paru -Ss nothing", 1, "")
+            .fail(
+                "brew search --desc --eval-all nothing",
+                1,
+                "Error: No formulae or casks found for \"nothing\".",
+            )
+            .fail(
+                "paru -Ss broken",
+                1,
+                "error: failed to initialize alpm library",
+            )
+            .fail(
+                "brew search --desc --eval-all broken",
+                1,
+                "Error: Failed to download https://formulae.brew.sh",
+            ),
+    ));
+
+    assert!(Packager::paru().search("nothing").unwrap().is_empty());
+    assert!(Packager::brew().search("nothing").unwrap().is_empty());
+
+    for pkgr in [Packager::paru(), Packager::brew()] {
+        let err = pkgr.search("broken").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(YumaError::Command { .. })
+        ));
+    }
+}
@@ -0,0 +1,377 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{
+    version_matches, GenericName, PackageBackend, PackageInfo, ParuPackager, Pin, SpecficName,
+};
+use yuma::deriv::pkg::builder::PkgBuilder;
+use yuma::deriv::pkg::list::AsPkgList;
+use yuma::deriv::pkg::upgrade::UpgradePolicy;
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+use common::{scratch, InHouse};
+
+#[test]
+fn matching_versions() {
+    assert!(version_matches("20", "20"));
+    assert!(version_matches("20", "20.11.1-1nodesource1"));
+    assert!(version_matches("1.3", "1:1.3.1-1"));
+    assert!(!version_matches("20", "200.1"));
+    assert!(!version_matches("20", "21.0.0"));
+
+    assert!(Pin::default().matches("1.0"));
+}
+
+#[test]
+fn builder_pins() {
+    let pkgs = "nodejs".b().version("20").hold().list();
+    assert_eq!(
+        pkgs[0].pin,
+        Pin {
+            version: Some("20".into()),
+            hold: true,
+        }
+    );
+
+    // merging keeps each package's pin to itself
+    let pkgs = ["nodejs".b().version("20"), "git".b()].b().list();
+    assert_eq!(pkgs.len(), 2);
+    assert_eq!(pkgs[0].names, ["nodejs"]);
+    assert_eq!(pkgs[0].pin.version.as_deref(), Some("20"));
+    assert_eq!(pkgs[1].names, ["git"]);
+    assert!(pkgs[1].pin.is_none());
+}
+
+#[test]
+fn paru_holds_through_ignore_pkg() {
+    let dir = scratch("pacman");
+    let conf = dir.join("pacman.conf");
+    fs::write(
+        &conf,
+        "[options]\nHoldPkg     = pacman glibc\nIgnorePkg = linux\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n",
+    )
+    .unwrap();
+
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    let paru = ParuPackager::with_config(&conf);
+    assert_eq!(paru.list_held().unwrap(), ["linux"]);
+
+    paru.hold(vec!["nodejs".into()]).unwrap();
+    let calls = runner.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].args.contains(&conf.display().to_string()));
+    assert_eq!(
+        calls[0].stdin.as_deref(),
+        Some("[options]\nHoldPkg     = pacman glibc\nIgnorePkg = linux nodejs\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n")
+    );
+
+    // holding again changes nothing
+    paru.hold(vec!["linux".into()]).unwrap();
+    assert_eq!(runner.calls().len(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn paru_refuses_other_versions() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .fail(
+                "paru -Qi nodejs",
+                1,
+                "error: package 'nodejs' was not found",
+            )
+            .reply(
+                "paru -Si nodejs",
+                "Repository      : extra\nName            : nodejs\nVersion         : 22.2.0-1\n\n",
+            ),
+    ));
+
+    let err = Packager::paru()
+        .install_version("nodejs".into(), "20")
+        .unwrap_err();
+    assert!(err.to_string().contains("22.2.0-1"), "{err}");
+}
+
+#[test]
+fn apt_installs_matching_version() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "apt-cache madison nodejs",
+        "    nodejs | 22.2.0-1nodesource1 | https://deb.nodesource.com/node_22.x nodistro/main amd64 Packages\n    nodejs | 20.11.1-1nodesource1 | https://deb.nodesource.com/node_20.x nodistro/main amd64 Packages\n",
+    ));
+    cmd::set_runner(runner.clone());
+
+    let name = Packager::apt()
+        .install_version("nodejs".into(), "20")
+        .unwrap();
+    assert_eq!(name, "nodejs");
+    assert!(runner.lines()[1]
+        .ends_with("apt-get install --yes --allow-downgrades nodejs=20.11.1-1nodesource1"));
+}
+
+const NODE_20: &str = r#"{
+  "formulae": [{
+    "name": "node@20",
+    "tap": "homebrew/core",
+    "desc": "Platform built on V8 to build network applications",
+    "versions": { "stable": "20.11.1" },
+    "installed": [{ "version": "20.11.1", "installed_on_request": true }]
+  }],
+  "casks": []
+}"#;
+
+const NODE_20_OUTDATED: &str = r#"{
+  "formulae": [
+    { "name": "node@20", "installed_versions": ["20.11.1"], "current_version": "20.12.0" },
+    { "name": "git", "installed_versions": ["2.44.0"], "current_version": "2.45.0" }
+  ],
+  "casks": []
+}"#;
+
+#[test]
+fn brew_versions_are_formulae() {
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply("brew info --json=v2 node@20", NODE_20)
+            .fail(
+                "brew info --json=v2 node@20.11",
+                1,
+                "Error: No available formula with the name \"node@20.11\".",
+            ),
+    );
+    cmd::set_runner(runner.clone());
+
+    let name = Packager::brew()
+        .install_version("node".into(), "20")
+        .unwrap();
+    assert_eq!(name, "node@20");
+    Packager::brew().hold(vec![name]).unwrap();
+    assert_eq!(
+        runner.lines(),
+        [
+            "brew info --json=v2 node@20",
+            "brew install node@20",
+            "brew pin node@20"
+        ]
+    );
+
+    // only some versions have a formula
+    let err = Packager::brew()
+        .install_version("node".into(), "20.11")
+        .unwrap_err();
+    assert!(err.to_string().contains("no node@20.11 formula"), "{err}");
+}
+
+#[test]
+fn brew_pins_by_formula() {
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply("brew info --json=v2 node@20", NODE_20)
+            .reply("brew info --json=v2 --installed", NODE_20)
+            .reply("brew list", "node@20\nstale\n")
+            .reply("brew leaves", "node@20\nstale\n")
+            .reply("brew outdated --json=v2", NODE_20_OUTDATED),
+    );
+    cmd::set_runner(runner.clone());
+
+    {
+        let mut ctx = YumaCtx::new();
+        ctx.run_mode(RunMode::AssumeYes);
+        ctx.add("node".b().version("20").with_packager(Packager::brew()));
+        ctx.update().unwrap();
+
+        let plan = ctx.upgrade_plan(UpgradePolicy::Declared).unwrap();
+        let names: Vec<_> = plan.upgrades().map(|(_, u)| u.name.as_str()).collect();
+        assert_eq!(names, ["node@20"]);
+    }
+    // other tests in this file write it too
+    let _ = fs::remove_file(".yumacache.json");
+
+    // the installed formula matches so only the undeclared leaf goes
+    let lines = runner.lines();
+    assert!(!lines.iter().any(|line| line.starts_with("brew install")));
+    let removed: Vec<_> = lines
+        .iter()
+        .filter(|l| l.starts_with("brew remove"))
+        .collect();
+    assert_eq!(removed, ["brew remove stale"]);
+}
+
+#[test]
+fn unsupported_backends_fail() {
+    let err = Packager::pipx()
+        .install_version("thing".into(), "1.0")
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(YumaError::Unsupported { .. })
+    ));
+    assert!(Packager::pipx().hold(vec!["thing".into()]).is_err());
+}
+
+/// Has node 20 installed and remembers which versions it was asked for.
+#[derive(Debug, Default)]
+struct Node {
+    asked: Mutex<Vec<String>>,
+}
+
+impl PackageBackend for Node {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(vec!["nodejs".into()])
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn install(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Ok(())
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        name.into()
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        Ok(vec![PackageInfo {
+            name: "nodejs".into(),
+            version: "20.11.1".into(),
+            description: None,
+            repository: None,
+            installed_size: None,
+            reason: None,
+        }])
+    }
+
+    fn install_version(&self, pkg: SpecficName, version: &str) -> Result<SpecficName> {
+        self.asked.lock().unwrap().push(format!("{pkg} {version}"));
+        Ok(pkg)
+    }
+}
+
+#[test]
+fn matching_versions_are_left_alone() {
+    let backend = Arc::new(Node::default());
+    let pkgr = Packager::register("node", backend.clone()).unwrap();
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::AssumeYes);
+    ctx.add("nodejs".b().version("20").with_packager(pkgr.clone()));
+    ctx.add("python".b().version("3.12").with_packager(pkgr));
+    ctx.update().unwrap();
+
+    assert_eq!(*backend.asked.lock().unwrap(), ["python 3.12"]);
+}
+
+/// Installs pinned packages as `name@version` and keeps track of holds.
+#[derive(Debug)]
+struct Holding {
+    pkgs: InHouse,
+    held: Mutex<Vec<String>>,
+}
+
+impl PackageBackend for Holding {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        self.pkgs.list_installed()
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.pkgs.list_leaves()
+    }
+
+    fn install(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.pkgs.install(pkgs)
+    }
+
+    fn remove(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.pkgs.remove(pkgs)
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        name.into()
+    }
+
+    fn installed_info(&self) -> Result<Vec<PackageInfo>> {
+        Ok(vec![PackageInfo {
+            name: "node@20".into(),
+            version: "20.11.1".into(),
+            description: None,
+            repository: None,
+            installed_size: None,
+            reason: None,
+        }])
+    }
+
+    fn pinned_name(&self, pkg: &str, version: &str) -> Result<SpecficName> {
+        Ok(format!("{pkg}@{version}"))
+    }
+
+    fn hold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.held.lock().unwrap().extend(pkgs);
+        Ok(())
+    }
+
+    fn unhold(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.held
+            .lock()
+            .unwrap()
+            .retain(|name| !pkgs.contains(name));
+        Ok(())
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        Ok(self.held.lock().unwrap().clone())
+    }
+}
+
+#[test]
+fn holds_follow_the_config() {
+    let backend = Arc::new(Holding {
+        pkgs: InHouse::with(&["node@20", "git"]),
+        held: Mutex::new(vec!["linux".into()]),
+    });
+    let pkgr = Packager::register("holding", backend.clone()).unwrap();
+    let update = |pkgs: Vec<PkgBuilder>| {
+        let mut ctx = YumaCtx::new();
+        ctx.dry_run();
+        ctx.run_mode(RunMode::AssumeYes);
+        for pkg in pkgs {
+            ctx.add(pkg.with_packager(pkgr.clone()));
+        }
+        ctx.update().unwrap();
+        backend.list_held().unwrap()
+    };
+
+    // the installed formula is held, not the bare name
+    let held = update(vec!["node".b().version("20").hold(), "git".b().hold()]);
+    assert_eq!(held, ["linux", "node@20", "git"]);
+
+    // dropping a hold releases it, holds made by hand stay
+    let held = update(vec!["node".b().version("20"), "git".b().hold()]);
+    assert_eq!(held, ["linux", "git"]);
+    let held = update(vec!["git".b()]);
+    assert_eq!(held, ["linux"]);
+
+    // pruning a held package releases it first
+    update(vec!["git".b().hold()]);
+    {
+        let mut ctx = YumaCtx::new();
+        ctx.run_mode(RunMode::AssumeYes);
+        ctx.add("node".b().version("20").with_packager(pkgr.clone()));
+        ctx.update().unwrap();
+    }
+    // other tests in this file write it too
+    let _ = fs::remove_file(".yumacache.json");
+    assert_eq!(backend.list_installed().unwrap(), ["node@20"]);
+    assert_eq!(backend.list_held().unwrap(), ["linux"]);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{
+    GenericName, PackageBackend, PackagerType, ParuPackager, SpecficName, Upgrade,
+};
+use yuma::deriv::pkg::upgrade::UpgradePolicy;
+use yuma::prelude::*;
+
+use common::scratch;
+
+fn upgrade(name: &str, old: &str, new: &str) -> Upgrade {
+    Upgrade {
+        name: name.into(),
+        old: old.into(),
+        new: new.into(),
+    }
+}
+
+/// Has a fixed set of pending upgrades and remembers what it upgraded.
+#[derive(Debug, Default)]
+struct Outdated {
+    upgraded: Mutex<Vec<String>>,
+}
+
+impl PackageBackend for Outdated {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn install(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove(&self, _pkgs: Vec<SpecficName>) -> Result<()> {
+        Ok(())
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        format!("{name:?}")
+    }
+
+    fn list_held(&self) -> Result<Vec<SpecficName>> {
+        Ok(vec!["linux".into()])
+    }
+
+    fn list_upgrades(&self) -> Result<Vec<Upgrade>> {
+        Ok(vec![
+            upgrade("linux", "6.9.1", "6.9.2"),
+            upgrade("nodejs", "20.11.1", "22.2.0"),
+            upgrade("ripgrep", "14.0.0", "14.1.0"),
+            upgrade("zlib", "1.3", "1.3.1"),
+        ])
+    }
+
+    fn upgrade(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.upgraded.lock().unwrap().extend(pkgs);
+        Ok(())
+    }
+}
+
+fn names(ctx: &YumaCtx, policy: UpgradePolicy) -> Vec<String> {
+    let plan = ctx.upgrade_plan(policy).unwrap();
+    plan.upgrades().map(|(_, u)| u.name.clone()).collect()
+}
+
+#[test]
+fn policies() {
+    let backend = Arc::new(Outdated::default());
+    let pkgr = Packager::register("outdated", backend.clone()).unwrap();
+
+    let mut ctx = YumaCtx::stub();
+    ctx.add(["linux", "ripgrep"].b().with_packager(pkgr.clone()));
+    ctx.add("nodejs".b().version("20").with_packager(pkgr));
+
+    assert_eq!(
+        names(&ctx, UpgradePolicy::All),
+        ["linux", "nodejs", "ripgrep", "zlib"]
+    );
+    // linux is held and node 22 breaks the pin
+    assert_eq!(names(&ctx, UpgradePolicy::Declared), ["ripgrep"]);
+    assert_eq!(names(&ctx, UpgradePolicy::ExcludeHeld), ["ripgrep", "zlib"]);
+
+    let plan = ctx.upgrade_plan(UpgradePolicy::ExcludeHeld).unwrap();
+    let (ptype, first) = plan.upgrades().next().unwrap();
+    assert_eq!(ptype, &PackagerType::Custom("outdated".into()));
+    assert_eq!(first, &upgrade("ripgrep", "14.0.0", "14.1.0"));
+
+    plan.apply().unwrap();
+    assert_eq!(*backend.upgraded.lock().unwrap(), ["ripgrep", "zlib"]);
+}
+
+#[test]
+fn paru_upgrades_all_at_once() {
+    let dir = scratch("paru-upgrades");
+    let conf = dir.join("pacman.conf");
+    fs::write(&conf, "[options]\nIgnorePkg = linux\n").unwrap();
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "checkupdates",
+                "linux 6.9.1.arch1-1 -> 6.9.2.arch1-1 [ignored]\nripgrep 14.0.0-1 -> 14.1.0-1\nzlib 1:1.3-1 -> 1:1.3.1-1\n",
+            )
+            .reply("paru -Qua", "paru-bin 2.0.3-1 -> 2.0.4-1\n"),
+    );
+    cmd::set_runner(runner.clone());
+
+    let paru = ParuPackager::with_config(&conf);
+    let upgrades = paru.list_upgrades().unwrap();
+    assert_eq!(upgrades[1], upgrade("ripgrep", "14.0.0-1", "14.1.0-1"));
+    assert_eq!(upgrades[3], upgrade("paru-bin", "2.0.3-1", "2.0.4-1"));
+
+    // leaving out zlib would be a partial upgrade, the aur and held linux
+    // can be left out
+    let err = paru.upgrade(vec!["ripgrep".into()]).unwrap_err();
+    assert!(err.to_string().contains("zlib"));
+    paru.upgrade(vec!["ripgrep".into(), "zlib".into()]).unwrap();
+    assert!(runner
+        .lines()
+        .last()
+        .unwrap()
+        .ends_with("paru -Syu --ignore paru-bin"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn apt_upgradable() {
+    cmd::set_runner(Arc::new(ScriptedRunner::new().reply(
+        "apt list --upgradable",
+        "Listing...\ncurl/stable-security 7.88.1-10+deb12u6 amd64 [upgradable from: 7.88.1-10+deb12u5]\n",
+    )));
+
+    assert_eq!(
+        Packager::apt().list_upgrades().unwrap(),
+        [upgrade("curl", "7.88.1-10+deb12u5", "7.88.1-10+deb12u6")]
+    );
+}
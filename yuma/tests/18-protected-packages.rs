@@ -0,0 +1,27 @@
+use yuma::deriv::packager::is_protected;
+use yuma::prelude::*;
+
+#[test]
+fn patterns() {
+    let patterns = ["linux-image-*", "sudo"];
+    assert!(is_protected("linux-image-amd64", &patterns));
+    assert!(is_protected("sudo", &patterns));
+    assert!(!is_protected("sudo-rs", &patterns));
+    assert!(!is_protected("linux-headers-amd64", &patterns));
+}
+
+#[test]
+fn backend_defaults() {
+    let paru = Packager::paru().protected();
+    for name in ["linux", "base", "grub", "pacman", "paru"] {
+        assert!(is_protected(name, &paru), "{name} is not protected");
+    }
+    assert!(!is_protected("neovim", &paru));
+
+    assert!(is_protected(
+        "linux-image-6.1.0-21-amd64",
+        &Packager::apt().protected()
+    ));
+    assert!(is_protected("kernel-core", &Packager::dnf().protected()));
+    assert!(Packager::fake().protected().is_empty());
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{GenericName, PackageBackend, SpecficName};
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+use common::InHouse;
+
+#[test]
+fn paru_cascade() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "pacman -Rns --print --print-format %n python-foo",
+        "python-foo\npython-bar\n",
+    ));
+    cmd::set_runner(runner);
+
+    let plan = Packager::paru()
+        .removal_plan(&["python-foo".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["python-foo", "python-bar"]);
+}
+
+#[test]
+fn paru_still_required() {
+    let runner = Arc::new(ScriptedRunner::new().fail(
+        "pacman -Rns --print --print-format %n glibc",
+        1,
+        "error: failed to prepare transaction (could not satisfy dependencies)\n",
+    ));
+    cmd::set_runner(runner);
+
+    assert!(Packager::paru()
+        .removal_plan(&["glibc".to_string()])
+        .is_err());
+}
+
+#[test]
+fn apt_simulated() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "apt-get remove --simulate libfoo1",
+        "Reading package lists...\nRemv foo-utils [1.2-3]\nRemv libfoo1 [1.2-3]\n",
+    ));
+    cmd::set_runner(runner);
+
+    let plan = Packager::apt()
+        .removal_plan(&["libfoo1".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["foo-utils", "libfoo1"]);
+}
+
+const DNF_REMOVE: &str = "\
+Dependencies resolved.
+================================================================================
+ Package             Arch        Version             Repository           Size
+================================================================================
+Removing:
+ python3-foo         noarch      1.2-3.fc39          @fedora              42 k
+Removing unused dependencies:
+ python3-bar         noarch      0.4-1.fc39          @fedora              12 k
+
+Transaction Summary
+================================================================================
+Remove  2 Packages
+
+Freed space: 54 k
+Operation aborted.
+";
+
+#[test]
+fn dnf_assumeno() {
+    // dnf needs root to plan a removal and exits with 1 when answered no
+    let line = "dnf remove --assumeno python3-foo";
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply(line, DNF_REMOVE)
+            .reply(format!("sudo {line}"), DNF_REMOVE),
+    );
+    cmd::set_runner(runner);
+
+    let plan = Packager::dnf()
+        .removal_plan(&["python3-foo".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["python3-foo", "python3-bar"]);
+}
+
+#[test]
+fn dnf_still_required() {
+    let line = "dnf remove --assumeno glibc";
+    let stderr = "Error: Problem: The operation would result in removing the following protected packages: dnf\n";
+    let runner = Arc::new(ScriptedRunner::new().fail(line, 1, stderr).fail(
+        format!("sudo {line}"),
+        1,
+        stderr,
+    ));
+    cmd::set_runner(runner);
+
+    assert!(Packager::dnf()
+        .removal_plan(&["glibc".to_string()])
+        .is_err());
+}
+
+#[test]
+fn xbps_dry_run() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "xbps-remove --recursive --dry-run python3-foo",
+        "python3-foo-1.2_3 remove noarch https://repo-default.voidlinux.org/current 42KB\n\
+         python3-bar-0.4_1 remove noarch https://repo-default.voidlinux.org/current 12KB\n",
+    ));
+    cmd::set_runner(runner);
+
+    let plan = Packager::xbps()
+        .removal_plan(&["python3-foo".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["python3-foo", "python3-bar"]);
+}
+
+#[test]
+fn apk_simulated() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "apk del --simulate py3-foo",
+        "(1/2) Purging py3-foo (1.2-r3)\n(2/2) Purging py3-bar (0.4-r1)\nOK: 12 MiB in 40 packages\n",
+    ));
+    cmd::set_runner(runner);
+
+    let plan = Packager::apk()
+        .removal_plan(&["py3-foo".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["py3-foo", "py3-bar"]);
+}
+
+#[test]
+fn no_cascade_by_default() {
+    let plan = Packager::fake()
+        .removal_plan(&["ripgrep".to_string()])
+        .unwrap();
+    assert_eq!(plan, ["ripgrep"]);
+}
+
+/// Removing `stale` would also take `keep` with it.
+#[derive(Debug)]
+struct Tangled(InHouse);
+
+impl PackageBackend for Tangled {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        self.0.list_installed()
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.0.list_leaves()
+    }
+
+    fn install(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.0.install(pkgs)
+    }
+
+    fn remove(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.0.remove(pkgs)
+    }
+
+    fn removal_plan(&self, pkgs: &[SpecficName]) -> Result<Vec<SpecficName>> {
+        let mut plan = pkgs.to_vec();
+        if pkgs.iter().any(|name| name == "stale") {
+            plan.push("keep".into());
+        }
+        Ok(plan)
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        self.0.resolve_name(name)
+    }
+}
+
+#[test]
+fn prunes_around_wanted() {
+    let backend = Arc::new(Tangled(InHouse::with(&["stale", "old", "keep"])));
+    let pkgr = Packager::register("tangled", backend.clone()).unwrap();
+
+    {
+        let mut ctx = YumaCtx::new();
+        ctx.run_mode(RunMode::AssumeYes);
+        ctx.add(["keep", "new"].b().with_packager(pkgr));
+        ctx.update().unwrap();
+    }
+    fs::remove_file(".yumacache.json").unwrap();
+
+    // only the leaf that would take a declared package with it is kept
+    assert_eq!(backend.list_installed().unwrap(), ["stale", "keep", "new"]);
+}
@@ -0,0 +1,83 @@
+use std::{fs, sync::Arc};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+#[test]
+fn paru_orphans() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("pacman -Qdtq", "python-setuptools\nmeson\n"),
+    ));
+    assert_eq!(
+        Packager::paru().list_orphans().unwrap(),
+        ["python-setuptools", "meson"]
+    );
+
+    // pacman exits with 1 when there are none
+    cmd::set_runner(Arc::new(ScriptedRunner::new().fail("pacman -Qdtq", 1, "")));
+    assert!(Packager::paru().list_orphans().unwrap().is_empty());
+}
+
+#[test]
+fn apt_orphans() {
+    cmd::set_runner(Arc::new(ScriptedRunner::new().reply(
+        "apt-get autoremove --simulate",
+        "Reading package lists...\nRemv libfoo1 [1.2-3]\nRemv libbar2 [0.9]\n",
+    )));
+    assert_eq!(
+        Packager::apt().list_orphans().unwrap(),
+        ["libfoo1", "libbar2"]
+    );
+}
+
+#[test]
+fn brew_orphans() {
+    cmd::set_runner(Arc::new(ScriptedRunner::new().reply(
+        "brew autoremove --dry-run",
+        "==> Would autoremove 2 unneeded formulae:\nlibyaml\nm4\n",
+    )));
+    assert_eq!(Packager::brew().list_orphans().unwrap(), ["libyaml", "m4"]);
+}
+
+#[test]
+fn unsupported() {
+    let err = Packager::pipx().list_orphans().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(YumaError::Unsupported { .. })
+    ));
+}
+
+#[test]
+fn orphans_keep_wanted() {
+    let plan = "pacman -Rns --print --print-format %n";
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply("paru -Qq", "python-bar\n")
+            .reply("pacman -Qdtq", "python-foo\nmeson\n")
+            .reply(
+                format!("{plan} python-foo meson"),
+                "python-foo\npython-bar\nmeson\n",
+            )
+            .reply(format!("{plan} python-foo"), "python-foo\npython-bar\n")
+            .reply(format!("{plan} meson"), "meson\n"),
+    );
+    cmd::set_runner(runner.clone());
+
+    {
+        let mut ctx = YumaCtx::new();
+        ctx.run_mode(RunMode::AssumeYes);
+        ctx.clean_orphans();
+        ctx.add("python-bar".b().with_packager(Packager::paru()));
+        ctx.update().unwrap();
+    }
+    fs::remove_file(".yumacache.json").unwrap();
+
+    // python-foo would take the declared python-bar with it
+    let lines = runner.lines();
+    assert!(
+        lines.last().unwrap().ends_with("-Rns --noconfirm meson"),
+        "{lines:?}"
+    );
+}
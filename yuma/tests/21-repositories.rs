@@ -0,0 +1,207 @@
+mod common;
+
+use std::{fs, sync::Arc};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{BrewPackager, FlatpakScope, PackageBackend, ParuPackager, Repo};
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+use common::scratch;
+
+const PACMAN_CONF: &str =
+    "[options]\nHoldPkg = pacman glibc\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+
+#[test]
+fn pacman_repositories() {
+    let dir = scratch("repos");
+    let conf = dir.join("pacman.conf");
+    fs::write(&conf, PACMAN_CONF).unwrap();
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    let paru = ParuPackager::with_config(&conf);
+    assert!(paru.list_repos().unwrap().is_empty());
+
+    let chaotic = Repo::new("chaotic-aur")
+        .key("3056513887B78AEB")
+        .option("Include", "/etc/pacman.d/chaotic-mirrorlist");
+    paru.add_repo(&chaotic).unwrap();
+
+    let lines = runner.lines();
+    assert!(lines[0].ends_with("pacman-key --recv-keys 3056513887B78AEB"));
+    assert!(lines[1].ends_with("pacman-key --lsign-key 3056513887B78AEB"));
+    // syncing only the databases would be a partial upgrade
+    assert_eq!(lines.len(), 3);
+    let written = runner.calls()[2].stdin.clone().unwrap();
+    assert_eq!(
+        written,
+        format!("{PACMAN_CONF}\n# BEGIN yuma repositories\n[chaotic-aur]\nInclude = /etc/pacman.d/chaotic-mirrorlist\n# END yuma repositories\n")
+    );
+
+    // the next install syncs the new database along with a full upgrade
+    paru.install(vec!["firefox".into()]).unwrap();
+    paru.install(vec!["mpv".into()]).unwrap();
+    let lines = runner.lines();
+    assert!(lines[3].ends_with("paru -Syu --needed firefox"));
+    assert!(lines[4].ends_with("paru -S --needed mpv"));
+
+    // nothing changes when the repository is already there
+    fs::write(&conf, &written).unwrap();
+    assert_eq!(paru.list_repos().unwrap(), ["chaotic-aur"]);
+    paru.add_repo(&chaotic).unwrap();
+    assert_eq!(runner.calls().len(), 5);
+
+    paru.remove_repos(vec!["chaotic-aur".into()]).unwrap();
+    assert_eq!(
+        runner.calls()[5].stdin.as_deref(),
+        Some(format!("{PACMAN_CONF}\n").as_str())
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn apt_sources() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "curl -fsSL https://download.docker.com/linux/debian/gpg",
+        "-----BEGIN PGP PUBLIC KEY BLOCK-----\n",
+    ));
+    cmd::set_runner(runner.clone());
+
+    let docker = Repo::new("docker")
+        .url("https://download.docker.com/linux/debian")
+        .key("https://download.docker.com/linux/debian/gpg")
+        .option("suite", "bookworm")
+        .option("components", "stable");
+    Packager::apt().add_repo(&docker).unwrap();
+
+    // the key is armored so apt needs it in an .asc file
+    let calls = runner.calls();
+    assert!(calls[2]
+        .line()
+        .ends_with("dd of=/etc/apt/keyrings/yuma-docker.asc status=none"));
+    assert!(calls[3]
+        .args
+        .contains(&"/etc/apt/sources.list.d/yuma-docker.list".to_string()));
+    assert_eq!(
+        calls[3].stdin.as_deref(),
+        Some("deb [signed-by=/etc/apt/keyrings/yuma-docker.asc] https://download.docker.com/linux/debian bookworm stable\n")
+    );
+    assert!(calls[4].line().ends_with("apt-get update"));
+
+    let err = Packager::apt().add_repo(&Repo::new("docker")).unwrap_err();
+    assert!(err.to_string().contains("needs a url"));
+}
+
+#[test]
+fn brew_taps() {
+    let runner = Arc::new(
+        ScriptedRunner::new().reply("brew tap", "homebrew/core\nhashicorp/tap\nme/by-hand\n"),
+    );
+    cmd::set_runner(runner.clone());
+    let dir = scratch("taps");
+    let brew = BrewPackager::with_state(dir.join("taps.json"));
+
+    // taps yuma didn't add are never offered for removal
+    assert!(brew.list_repos().unwrap().is_empty());
+
+    brew.add_repo(&Repo::new("hashicorp/tap")).unwrap();
+    brew.add_repo(&Repo::new("me/tools").url("https://example.com/me/homebrew-tools"))
+        .unwrap();
+    assert_eq!(
+        runner.lines(),
+        [
+            "brew tap",
+            "brew tap",
+            "brew tap",
+            "brew tap me/tools https://example.com/me/homebrew-tools",
+        ]
+    );
+    assert_eq!(brew.list_repos().unwrap(), ["hashicorp/tap"]);
+
+    brew.remove_repos(vec!["hashicorp/tap".into()]).unwrap();
+    assert_eq!(runner.lines().last().unwrap(), "brew untap hashicorp/tap");
+    assert!(brew.list_repos().unwrap().is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn flatpak_only_adds_its_remote() {
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    let flathub = Packager::flatpak(FlatpakScope::User, "flathub");
+    assert!(flathub
+        .add_repo(&Repo::new("fedora").url("oci+https://registry.fedoraproject.org"))
+        .is_err());
+
+    flathub
+        .add_repo(&Repo::new("flathub").url("https://dl.flathub.org/repo/flathub.flatpakrepo"))
+        .unwrap();
+    assert_eq!(
+        runner.lines(),
+        [
+            "flatpak remotes --user --columns=name",
+            "flatpak remote-add --user --if-not-exists --comment=Managed by yuma flathub https://dl.flathub.org/repo/flathub.flatpakrepo"
+        ]
+    );
+}
+
+#[test]
+fn flatpak_updates_existing_remote() {
+    let runner = Arc::new(ScriptedRunner::new().reply(
+        "flatpak remotes --system --columns=name",
+        "fedora\nflathub\n",
+    ));
+    cmd::set_runner(runner.clone());
+
+    Packager::flatpak(FlatpakScope::System, "flathub")
+        .add_repo(
+            &Repo::new("flathub")
+                .url("https://mirror.example.org/flathub")
+                .key("/etc/flathub.gpg"),
+        )
+        .unwrap();
+    assert_eq!(
+        runner.lines()[1],
+        "flatpak remote-modify --system --url=https://mirror.example.org/flathub --comment=Managed by yuma --gpg-import=/etc/flathub.gpg flathub"
+    );
+}
+
+#[test]
+fn unsupported() {
+    let err = Packager::pipx().list_repos().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(YumaError::Unsupported { .. })
+    ));
+}
+
+#[test]
+fn refused_repositories_skip_installs() {
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    let flathub = Packager::flatpak(FlatpakScope::User, "flathub");
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::Answers(
+        [
+            ("add_repos".to_string(), false),
+            ("install".to_string(), true),
+        ]
+        .into(),
+    ));
+    ctx.repo(
+        flathub.clone(),
+        Repo::new("flathub").url("https://dl.flathub.org/repo/flathub.flatpakrepo"),
+    );
+    ctx.add("org.gimp.GIMP".b().with_packager(flathub));
+    ctx.update().unwrap();
+
+    let lines = runner.lines();
+    assert!(!lines.iter().any(|line| line.contains("remote-add")));
+    assert!(!lines.iter().any(|line| line.contains("flatpak install")));
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use std::env;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::PackageBackend;
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+use common::{scratch, InHouse};
+
+/// Runs an update of `tool` in `mode` and returns what got installed.
+fn update_in(mode: RunMode) -> Vec<String> {
+    let backend = Arc::new(InHouse::default());
+    let pkgr = Packager::register("recorder", backend.clone()).unwrap();
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(mode);
+    ctx.add("tool".b().with_packager(pkgr));
+    ctx.update().unwrap();
+
+    backend.list_installed().unwrap()
+}
+
+#[test]
+fn assume_yes_and_no() {
+    assert_eq!(update_in(RunMode::AssumeYes), ["tool"]);
+    assert!(update_in(RunMode::AssumeNo).is_empty());
+}
+
+#[test]
+fn answers_from_file() {
+    let dir = scratch("answers");
+    let path = dir.join("answers.json");
+    fs::write(&path, r#"{ "install": true, "remove": false }"#).unwrap();
+    let mode = RunMode::answers_from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(update_in(mode), ["tool"]);
+
+    // questions without an answer are answered with no
+    let mode = RunMode::Answers([("remove".to_string(), true)].into());
+    assert!(update_in(mode).is_empty());
+
+    assert!(RunMode::answers_from_file(&path).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn native_flags() {
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    Packager::paru().install(vec!["fd".into()]).unwrap();
+    assert_eq!(runner.lines(), ["paru -S --needed fd"]);
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::AssumeYes);
+    ctx.add("fd".b().with_packager(Packager::paru()));
+    ctx.update().unwrap();
+    assert_eq!(
+        runner.lines().last().unwrap(),
+        "paru -S --noconfirm --needed fd"
+    );
+
+    // the mode stays with the ctx
+    Packager::paru().remove(vec!["fd".into()]).unwrap();
+    assert_eq!(runner.lines().last().unwrap(), "paru -Rns fd");
+}
+
+/// Runs itself again with stdin closed so there surely is no terminal.
+#[test]
+fn no_terminal_fails() {
+    if env::var_os("YUMA_TEST_NO_TERMINAL").is_some() {
+        let backend = Arc::new(InHouse::default());
+        let pkgr = Packager::register("unanswered", backend.clone()).unwrap();
+
+        let mut ctx = YumaCtx::new();
+        ctx.dry_run();
+        ctx.add("tool".b().with_packager(pkgr));
+        let err = ctx.update().unwrap_err();
+        assert!(err.to_string().contains("No terminal"));
+        assert!(backend.list_installed().unwrap().is_empty());
+        return;
+    }
+
+    let out = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "no_terminal_fails"])
+        .env("YUMA_TEST_NO_TERMINAL", "1")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    assert!(stdout.contains("1 passed"), "{stdout}");
+}
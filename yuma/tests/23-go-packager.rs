@@ -0,0 +1,66 @@
+mod common;
+
+use std::{fs, sync::Arc};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{GoPackager, PackageBackend};
+
+use common::scratch;
+
+const GOPLS: &str = "/bin/gopls: go1.22.0
+\tpath\tgolang.org/x/tools/gopls
+\tmod\tgolang.org/x/tools/gopls\tv0.15.0\th1:abc=
+\tdep\tgolang.org/x/mod\tv0.15.0\th1:def=
+";
+
+#[test]
+fn versions_from_binaries() {
+    let dir = scratch("go");
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    fs::write(bin.join("gopls"), "").unwrap();
+    fs::write(bin.join("notes.txt"), "").unwrap();
+
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply("go env GOBIN GOPATH", format!("\n{}\n", dir.display()))
+            .reply(
+                format!("go version -m {}", bin.join("gopls").display()),
+                GOPLS,
+            )
+            .fail(
+                format!("go version -m {}", bin.join("notes.txt").display()),
+                1,
+                "not a go binary",
+            ),
+    );
+    cmd::set_runner(runner);
+
+    let go = GoPackager;
+    assert_eq!(go.list_installed().unwrap(), ["golang.org/x/tools/gopls"]);
+    let info = go.installed_info().unwrap();
+    assert_eq!(info.len(), 1);
+    assert_eq!(info[0].name, "golang.org/x/tools/gopls");
+    assert_eq!(info[0].version, "v0.15.0");
+}
+
+#[test]
+fn versions_in_names() {
+    let go = GoPackager;
+    let split = |name: &str| go.split_version(name.to_string());
+    assert_eq!(
+        split("golang.org/x/tools/gopls@v0.15.0"),
+        (
+            "golang.org/x/tools/gopls".to_string(),
+            Some("v0.15.0".to_string())
+        )
+    );
+    assert_eq!(
+        split("golang.org/x/tools/gopls@latest"),
+        ("golang.org/x/tools/gopls".to_string(), None)
+    );
+    assert_eq!(
+        split("golang.org/x/tools/gopls"),
+        ("golang.org/x/tools/gopls".to_string(), None)
+    );
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+use yuma::prompt::RunMode;
+
+// the status abbreviation is padded to three characters
+const DPKG_QUERY: &str = "adduser\tii \ncurl\tii \nlibfoo1\trc \nvim\tiU \n";
+
+#[test]
+fn installed_and_leaves() {
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "dpkg-query --show --showformat=${Package}\t${db:Status-Abbrev}\n",
+                DPKG_QUERY,
+            )
+            .reply("apt-mark showmanual", "curl\nvim\n"),
+    );
+    cmd::set_runner(runner.clone());
+
+    // removed packages with leftover config and half installed ones don't count
+    assert_eq!(
+        Packager::apt().list_installed().unwrap(),
+        ["adduser", "curl"]
+    );
+    assert_eq!(Packager::apt().list_leaves().unwrap(), ["curl", "vim"]);
+
+    Packager::apt().install(vec!["fd-find".into()]).unwrap();
+    assert!(runner
+        .lines()
+        .last()
+        .unwrap()
+        .ends_with("env DEBIAN_FRONTEND=noninteractive apt-get install --yes fd-find"));
+}
+
+#[test]
+fn installed_info() {
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "dpkg-query --show --showformat=${Package}\t${db:Status-Abbrev}\t${Version}\t${Installed-Size}\t${binary:Summary}\n",
+                "curl\tii \t7.88.1-10\t500\tcommand line tool for transferring data with URL syntax\n\
+                 libcurl4\tii \t7.88.1-10\t800\teasy-to-use client-side URL transfer library\n\
+                 libfoo1\trc \t1.0\t10\tgone\n",
+            )
+            .reply("apt-mark showmanual", "curl\n"),
+    );
+    cmd::set_runner(runner);
+
+    let infos = Packager::apt().installed_info().unwrap();
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].name, "curl");
+    assert_eq!(infos[0].version, "7.88.1-10");
+    assert_eq!(infos[0].installed_size, Some(500 * 1024));
+    assert!(infos[0].is_explicit());
+    assert!(infos[1].is_installed() && !infos[1].is_explicit());
+}
+
+#[test]
+fn satisfied_pins_are_left_alone() {
+    let runner = Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "dpkg-query --show --showformat=${Package}\t${db:Status-Abbrev}\t${Version}\t${Installed-Size}\t${binary:Summary}\n",
+                "nodejs\tii \t20.11.1-1nodesource1\t200000\tevented I/O for V8 javascript\n",
+            )
+            .reply(
+                "dpkg-query --show --showformat=${Package}\t${db:Status-Abbrev}\n",
+                "nodejs\tii \n",
+            )
+            .reply("apt-mark showmanual", "nodejs\n"),
+    );
+    cmd::set_runner(runner.clone());
+
+    let mut ctx = YumaCtx::new();
+    ctx.dry_run();
+    ctx.run_mode(RunMode::AssumeYes);
+    ctx.add("nodejs".b().version("20").with_packager(Packager::apt()));
+    ctx.update().unwrap();
+
+    let lines = runner.lines();
+    assert!(
+        !lines
+            .iter()
+            .any(|line| line.contains("madison") || line.contains("install")),
+        "{lines:?}"
+    );
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+
+const DNF_SEARCH: &str = "\
+======================== Name Exactly Matched: ripgrep =========================
+ripgrep.x86_64 : Line oriented search tool using Rust's regex library
+======================= Name & Summary Matched: ripgrep ========================
+ripgrep-all.x86_64 : Ripgrep, but also search in PDFs, E-Books, Office documents
+";
+
+#[test]
+fn installed_and_leaves() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "rpm --query --all --queryformat=%{NAME}\n",
+                "bash\ngpg-pubkey\ngpg-pubkey\nripgrep\n",
+            )
+            .reply(
+                "dnf repoquery --userinstalled --queryformat=%{name}\n",
+                "ripgrep\n\n",
+            ),
+    ));
+
+    // imported signing keys show up as packages
+    assert_eq!(
+        Packager::dnf().list_installed().unwrap(),
+        ["bash", "ripgrep"]
+    );
+    assert_eq!(Packager::dnf().list_leaves().unwrap(), ["ripgrep"]);
+}
+
+#[test]
+fn search() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("dnf search --quiet ripgrep", DNF_SEARCH),
+    ));
+
+    let hits = Packager::dnf().search("ripgrep").unwrap();
+    let names: Vec<_> = hits.iter().map(|hit| hit.name.as_str()).collect();
+    assert_eq!(names, ["ripgrep", "ripgrep-all"]);
+    assert_eq!(
+        hits[0].description.as_deref(),
+        Some("Line oriented search tool using Rust's regex library")
+    );
+}
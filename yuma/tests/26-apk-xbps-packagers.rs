@@ -0,0 +1,64 @@
+mod common;
+
+use std::{fs, sync::Arc};
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::deriv::packager::{ApkPackager, PackageBackend};
+use yuma::prelude::*;
+
+use common::scratch;
+
+#[test]
+fn apk_installed() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("apk info --quiet", "alpine-base\nbusybox\nripgrep\n"),
+    ));
+    assert_eq!(
+        Packager::apk().list_installed().unwrap(),
+        ["alpine-base", "busybox", "ripgrep"]
+    );
+}
+
+#[test]
+fn apk_world() {
+    let world = scratch("apk-world").join("world");
+    fs::write(
+        &world,
+        "alpine-base\nripgrep>=14\nfd=9.0.0-r0\nneovim@edge\n",
+    )
+    .unwrap();
+
+    // constraints on world entries are not part of the name
+    assert_eq!(
+        ApkPackager::with_world(&world).list_leaves().unwrap(),
+        ["alpine-base", "ripgrep", "fd", "neovim"]
+    );
+    fs::remove_file(world).unwrap();
+}
+
+#[test]
+fn xbps_installed_and_leaves() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .reply(
+                "xbps-query --list-pkgs",
+                "ii base-system-0.114_2     Void Linux base system meta package\n\
+                 ii ripgrep-14.1.0_1        Fast line-oriented regex search tool\n\
+                 ii xdg-utils-1.2.1_1       Basic desktop integration functions\n",
+            )
+            .reply(
+                "xbps-query --list-manual-pkgs",
+                "base-system-0.114_2\nripgrep-14.1.0_1\n",
+            ),
+    ));
+
+    // the version is split off at the last dash
+    assert_eq!(
+        Packager::xbps().list_installed().unwrap(),
+        ["base-system", "ripgrep", "xdg-utils"]
+    );
+    assert_eq!(
+        Packager::xbps().list_leaves().unwrap(),
+        ["base-system", "ripgrep"]
+    );
+}
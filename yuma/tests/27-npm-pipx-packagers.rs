@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+
+const NPM_LIST: &str = r#"{
+  "name": "lib",
+  "dependencies": {
+    "corepack": { "version": "0.25.2", "overridden": false },
+    "npm": { "version": "10.5.0", "overridden": false },
+    "prettier": { "version": "3.2.5", "overridden": false }
+  }
+}"#;
+
+const PIPX_LIST: &str = r#"{
+  "pipx_spec_version": "0.1",
+  "venvs": {
+    "black": { "metadata": { "main_package": { "package": "black" } } },
+    "httpie": { "metadata": { "main_package": { "package": "httpie" } } }
+  }
+}"#;
+
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
+
+#[test]
+fn npm_globals() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("npm list --global --depth=0 --json", NPM_LIST),
+    ));
+
+    let npm = Packager::npm();
+    assert_eq!(
+        sorted(npm.list_installed().unwrap()),
+        ["corepack", "npm", "prettier"]
+    );
+    // what ships with node is never a leaf
+    assert_eq!(npm.list_leaves().unwrap(), ["prettier"]);
+
+    // an empty prefix has no dependencies at all
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("npm list --global --depth=0 --json", "{}"),
+    ));
+    assert!(npm.list_installed().unwrap().is_empty());
+}
+
+#[test]
+fn npm_problems_still_list() {
+    // unmet peer dependencies fail npm ls but it still prints the listing
+    cmd::set_runner(Arc::new(ScriptedRunner::new().exit(
+        "npm list --global --depth=0 --json",
+        1,
+        NPM_LIST,
+        "npm error code ELSPROBLEMS",
+    )));
+    let npm = Packager::npm();
+    assert_eq!(
+        sorted(npm.list_installed().unwrap()),
+        ["corepack", "npm", "prettier"]
+    );
+
+    cmd::set_runner(Arc::new(ScriptedRunner::new().fail(
+        "npm list --global --depth=0 --json",
+        1,
+        "npm error code EACCES",
+    )));
+    let err = npm.list_installed().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(YumaError::Command { .. })
+    ));
+}
+
+#[test]
+fn pipx_venvs() {
+    let runner = Arc::new(ScriptedRunner::new().reply("pipx list --json", PIPX_LIST));
+    cmd::set_runner(runner.clone());
+
+    let pipx = Packager::pipx();
+    assert_eq!(sorted(pipx.list_leaves().unwrap()), ["black", "httpie"]);
+
+    pipx.remove(vec!["black".into(), "httpie".into()]).unwrap();
+    let lines = runner.lines();
+    assert_eq!(
+        lines[1..],
+        ["pipx uninstall black", "pipx uninstall httpie"]
+    );
+}
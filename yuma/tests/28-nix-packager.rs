@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+
+/// `nix profile list --json` before nix 2.20, elements are only known by
+/// their index.
+const OLD_PROFILE: &str = r#"{
+  "version": 2,
+  "elements": [
+    {
+      "active": true,
+      "attrPath": "legacyPackages.x86_64-linux.ripgrep",
+      "originalUrl": "flake:nixpkgs",
+      "storePaths": ["/nix/store/abc-ripgrep-14.1.0"]
+    },
+    {
+      "active": true,
+      "storePaths": ["/nix/store/def-hello-2.12"]
+    },
+    {
+      "active": true,
+      "attrPath": "packages.x86_64-linux.default",
+      "originalUrl": "github:helix-editor/helix",
+      "storePaths": ["/nix/store/ghi-helix-24.03"]
+    }
+  ]
+}"#;
+
+/// Newer versions name every element.
+const NEW_PROFILE: &str = r#"{
+  "version": 3,
+  "elements": {
+    "helix": {
+      "active": true,
+      "attrPath": "packages.x86_64-linux.default",
+      "originalUrl": "github:helix-editor/helix",
+      "storePaths": ["/nix/store/ghi-helix-24.03"]
+    },
+    "ripgrep": {
+      "active": true,
+      "attrPath": "legacyPackages.x86_64-linux.ripgrep",
+      "originalUrl": "flake:nixpkgs",
+      "storePaths": ["/nix/store/abc-ripgrep-14.1.0"]
+    }
+  }
+}"#;
+
+fn profile(json: &str) -> Arc<ScriptedRunner> {
+    let runner = Arc::new(ScriptedRunner::new().reply("nix profile list --json", json));
+    cmd::set_runner(runner.clone());
+    runner
+}
+
+#[test]
+fn old_profile() {
+    let runner = profile(OLD_PROFILE);
+    let nix = Packager::nix();
+
+    // elements installed from a store path have no flake reference
+    assert_eq!(
+        nix.list_installed().unwrap(),
+        ["nixpkgs#ripgrep", "github:helix-editor/helix#default"]
+    );
+
+    nix.remove(vec!["github:helix-editor/helix#default".into()])
+        .unwrap();
+    assert_eq!(runner.lines().last().unwrap(), "nix profile remove 2");
+}
+
+#[test]
+fn new_profile() {
+    let runner = profile(NEW_PROFILE);
+    let nix = Packager::nix();
+
+    let mut installed = nix.list_installed().unwrap();
+    installed.sort();
+    assert_eq!(
+        installed,
+        ["github:helix-editor/helix#default", "nixpkgs#ripgrep"]
+    );
+
+    nix.remove(vec!["nixpkgs#ripgrep".into()]).unwrap();
+    assert_eq!(runner.lines().last().unwrap(), "nix profile remove ripgrep");
+}
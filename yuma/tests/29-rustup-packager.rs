@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use yuma::cmd::{self, ScriptedRunner};
+use yuma::prelude::*;
+
+const HOST: &str = "x86_64-unknown-linux-gnu";
+
+#[test]
+fn toolchains_components_and_targets() {
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new()
+            .reply("rustup show", format!("Default host: {HOST}\nrustup home:  /root/.rustup\n"))
+            .reply("rustup toolchain list", format!("stable-{HOST} (default)\n"))
+            .reply(
+                format!("rustup component list --installed --toolchain stable-{HOST}"),
+                format!(
+                    "cargo-{HOST}\nclippy-{HOST}\nrust-std-{HOST}\nrust-std-wasm32-unknown-unknown\nrustc-{HOST}\n"
+                ),
+            ),
+    ));
+
+    let rustup = Packager::rustup();
+    assert_eq!(
+        rustup.list_installed().unwrap(),
+        [
+            "stable/component/cargo",
+            "stable/component/clippy",
+            format!("stable/target/{HOST}").as_str(),
+            "stable/target/wasm32-unknown-unknown",
+            "stable/component/rustc",
+            "stable",
+        ]
+    );
+    // rustup won't remove the compiler, cargo or the host std
+    assert_eq!(
+        rustup.list_leaves().unwrap(),
+        [
+            "stable/component/clippy",
+            "stable/target/wasm32-unknown-unknown",
+            "stable",
+        ]
+    );
+
+    // removing the toolchain takes everything in it along
+    assert_eq!(
+        rustup
+            .removal_plan(&["stable/target/wasm32-unknown-unknown".into()])
+            .unwrap(),
+        ["stable/target/wasm32-unknown-unknown"]
+    );
+    let plan = rustup.removal_plan(&["stable".into()]).unwrap();
+    assert_eq!(plan.len(), 6);
+    assert!(plan.contains(&"stable/component/clippy".to_string()));
+
+    cmd::set_runner(Arc::new(
+        ScriptedRunner::new().reply("rustup toolchain list", "no installed toolchains\n"),
+    ));
+    assert!(rustup.list_installed().unwrap().is_empty());
+}
+
+#[test]
+fn names() {
+    let runner = Arc::new(ScriptedRunner::new());
+    cmd::set_runner(runner.clone());
+
+    let rustup = Packager::rustup();
+    rustup
+        .install(vec![
+            "nightly/target/wasm32-unknown-unknown".into(),
+            "nightly".into(),
+            "nightly/component/miri".into(),
+        ])
+        .unwrap();
+    // the toolchain comes first
+    assert_eq!(
+        runner.lines(),
+        [
+            "rustup toolchain install --profile minimal nightly",
+            "rustup target add --toolchain nightly wasm32-unknown-unknown",
+            "rustup component add --toolchain nightly miri",
+        ]
+    );
+
+    for name in ["nightly/tool/miri", "nightly/component"] {
+        let err = rustup.install(vec![name.into()]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(YumaError::InvalidPackage { .. })
+        ));
+    }
+    assert_eq!(runner.lines().len(), 3);
+}
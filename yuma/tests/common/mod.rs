@@ -0,0 +1,55 @@
+//! Fixtures shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use yuma::deriv::packager::{GenericName, PackageBackend, SpecficName};
+use yuma::prelude::*;
+
+/// An empty directory for a test, cleared of anything an earlier run left.
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yuma-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Pretends everything it installs stays installed as a leaf.
+#[derive(Debug, Default)]
+pub struct InHouse {
+    installed: Mutex<Vec<String>>,
+}
+
+impl InHouse {
+    /// Starts out with `pkgs` installed.
+    pub fn with(pkgs: &[&str]) -> Self {
+        Self {
+            installed: Mutex::new(pkgs.iter().map(ToString::to_string).collect()),
+        }
+    }
+}
+
+impl PackageBackend for InHouse {
+    fn list_installed(&self) -> Result<Vec<String>> {
+        Ok(self.installed.lock().unwrap().clone())
+    }
+
+    fn list_leaves(&self) -> Result<Vec<String>> {
+        self.list_installed()
+    }
+
+    fn install(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.installed.lock().unwrap().extend(pkgs);
+        Ok(())
+    }
+
+    fn remove(&self, pkgs: Vec<SpecficName>) -> Result<()> {
+        self.installed.lock().unwrap().retain(|p| !pkgs.contains(p));
+        Ok(())
+    }
+
+    fn resolve_name(&self, name: GenericName) -> SpecficName {
+        name.into()
+    }
+}